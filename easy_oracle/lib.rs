@@ -44,7 +44,14 @@ mod easy_oracle {
         badge_contract_options: Option<(AccountId, u32)>,
         attestation_verifier: attestation::Verifier,
         attestation_generator: attestation::Generator,
+        // The Github usernames linked before the other providers were supported
         linked_users: Mapping<String, ()>,
+        // Identity providers
+        linked_identities: Mapping<(Provider, String), ()>,
+        api_tokens: Mapping<Provider, String>,
+        // Claim verification, where a missing value means the default
+        allowed_ss58_prefixes: Mapping<(), Vec<u16>>,
+        require_signed_claims: Mapping<(), ()>,
     }
 
     /// Errors that can occur upon calling this contract.
@@ -63,6 +70,13 @@ mod easy_oracle {
         UsernameAlreadyInUse,
         AccountAlreadyInUse,
         FailedToIssueBadge,
+        BadChecksum,
+        WrongNetwork,
+        SignatureRequired,
+        InvalidClaimSignature,
+        OwnerMismatch,
+        InvalidGistMetadata,
+        InvalidSnippetMetadata,
     }
 
     /// Type alias for the contract's result type.
@@ -98,17 +112,93 @@ mod easy_oracle {
             Ok(())
         }
 
+        /// Sets the SS58 network prefixes accepted in the identity claims
+        ///
+        /// Only the admin can call it.
+        #[ink(message)]
+        pub fn set_allowed_ss58_prefixes(&mut self, prefixes: Vec<u16>) -> Result<()> {
+            let caller = self.env().caller();
+            if caller != self.admin {
+                return Err(Error::BadOrigin);
+            }
+            self.allowed_ss58_prefixes.insert((), &prefixes);
+            Ok(())
+        }
+
+        /// Returns the SS58 network prefixes accepted in the identity claims
+        #[ink(message)]
+        pub fn allowed_ss58_prefixes(&self) -> Vec<u16> {
+            self.allowed_ss58_prefixes
+                .get(())
+                .unwrap_or_else(|| DEFAULT_SS58_PREFIXES.to_vec())
+        }
+
+        /// Sets whether the identity claims must be signed by the claimed account
+        ///
+        /// Only the admin can call it. See [`claim_message`] for the message to sign.
+        #[ink(message)]
+        pub fn set_require_signed_claims(&mut self, required: bool) -> Result<()> {
+            let caller = self.env().caller();
+            if caller != self.admin {
+                return Err(Error::BadOrigin);
+            }
+            if required {
+                self.require_signed_claims.insert((), &());
+            } else {
+                self.require_signed_claims.remove(());
+            }
+            Ok(())
+        }
+
+        /// Returns whether the identity claims must be signed by the claimed account
+        #[ink(message)]
+        pub fn require_signed_claims(&self) -> bool {
+            self.require_signed_claims.contains(())
+        }
+
+        /// Sets the API token to confirm the proof owners with a provider, or removes it by `None`
+        ///
+        /// Only the admin can call it. The token is sent to the provider API as a bearer token for
+        /// Github, or as a private token for GitLab. Without a token, the Github API only allows
+        /// 60 requests per hour from each worker, while a token raises it to 5000. A token without
+        /// any permission is enough.
+        #[ink(message)]
+        pub fn set_api_token(&mut self, provider: Provider, token: Option<String>) -> Result<()> {
+            let caller = self.env().caller();
+            if caller != self.admin {
+                return Err(Error::BadOrigin);
+            }
+            match token {
+                Some(token) => self.api_tokens.insert(provider, &token),
+                None => self.api_tokens.remove(provider),
+            }
+            Ok(())
+        }
+
+        /// Returns the message for `account_id` to sign in the proof at `url`
+        ///
+        /// The identity handle and the proof id are normalized as in [`attest`] (e.g. the Github
+        /// username and the gist id are lowercased), so sign the returned message instead of
+        /// building it from the url. Returns an `InvalidUrl` error if the url isn't a supported
+        /// proof url.
+        #[ink(message)]
+        pub fn claim_message(&self, url: String, account_id: AccountId) -> Result<String> {
+            let (_, proof_url) = parse_proof_url(&url)?;
+            Ok(claim_message(&proof_url, &account_id))
+        }
+
         /// Redeems a POAP with a signed `attestation`. (callable)
         ///
-        /// The attestation must be created by [`attest_gist`] function. After the verification of
-        /// the attestation, the the sender account will the linked to a Github username. Then a
-        /// POAP redemption code will be allocated to the sender.
+        /// The attestation must be created by [`attest`] function. After the verification of the
+        /// attestation, the the sender account will the linked to the identity handle of the
+        /// provider (e.g. a Github username). Then a POAP redemption code will be allocated to the
+        /// sender.
         ///
-        /// Each blockchain account and github account can only be linked once.
+        /// Each blockchain account and identity can only be linked once.
         #[ink(message)]
         pub fn redeem(&mut self, attestation: attestation::Attestation) -> Result<()> {
             // Verify the attestation
-            let data: GistQuote = self
+            let data: IdentityQuote = self
                 .attestation_verifier
                 .verify_as(&attestation)
                 .ok_or(Error::InvalidSignature)?;
@@ -117,12 +207,13 @@ mod easy_oracle {
                 pink::warn!("No permission.");
                 return Err(Error::NoPermission);
             }
-            // The github username can only link to one account
-            if self.linked_users.contains(&data.username) {
+            // The identity can only link to one account
+            let identity = (data.provider, data.handle);
+            if self.is_linked(&identity) {
                 pink::warn!("Username alreay in use.");
                 return Err(Error::UsernameAlreadyInUse);
             }
-            self.linked_users.insert(&data.username, &());
+            self.linked_identities.insert(&identity, &());
             // Call the badges contract to issue the NFT
             let (contract, id) = self
                 .badge_contract_options
@@ -134,16 +225,27 @@ mod easy_oracle {
             pink::warn!("Badges.issue() result = {:?}", result);
             result.or(Err(Error::FailedToIssueBadge))
         }
+
+        /// Checks if the identity is linked, including the Github usernames in `linked_users`
+        fn is_linked(&self, identity: &(Provider, String)) -> bool {
+            let (provider, handle) = identity;
+            self.linked_identities.contains(identity)
+                || (*provider == Provider::GithubGist && self.linked_users.contains(handle))
+        }
     }
 
     impl SubmittableOracle for EasyOracle {
         // Queries
 
-        /// Attests a Github Gist by the raw file url. (Query only)
+        /// Attests an identity proof by the raw file url. (Query only)
         ///
-        /// It sends a HTTPS request to the url and extract an address from the claim ("This gist
-        /// is owned by address: 0x..."). Once the claim is verified, it returns a signed
-        /// attestation with the data `(username, account_id)`.
+        /// The url can be a Github Gist, a GitLab snippet, or a `/.well-known/` file on a domain
+        /// (see [`IdentityProvider`]). It sends a HTTPS request to the url and extract an address
+        /// from the claim (e.g. "This gist is owned by address: 0x..." or an SS58 address). If the
+        /// proof also has a signature ("Signature: 0x..."), it must be signed by the claimed
+        /// account over the message returned by [`EasyOracle::claim_message`]. The signature is
+        /// mandatory when `require_signed_claims` is set. Once the claim is verified, it returns a
+        /// signed attestation with the data `(provider, handle, account_id)`.
         ///
         /// The `Err` variant of the result is an encoded `Error` to simplify cross-contract calls.
         /// Particularly, when another contract wants to call us, they may not want to depend on
@@ -151,17 +253,34 @@ mod easy_oracle {
         #[ink(message)]
         fn attest(&self, url: String) -> core::result::Result<attestation::Attestation, Vec<u8>> {
             // Verify the URL
-            let gist_url = parse_gist_url(&url).map_err(|e| e.encode())?;
-            // Fetch the gist content
-            let resposne = http_get!(url);
-            if resposne.status_code != 200 {
-                return Err(Error::RequestFailed.encode());
-            }
-            let body = resposne.body;
+            let (provider, proof_url) = parse_proof_url(&url).map_err(|e| e.encode())?;
+            // Confirm the identity with the provider
+            let api_token = self.api_tokens.get(proof_url.provider);
+            provider
+                .verify_owner(&url, &proof_url, api_token.as_deref())
+                .map_err(|e| e.encode())?;
+            // Fetch the proof content
+            let body = provider.fetch(&url).map_err(|e| e.encode())?;
             // Verify the claim and extract the account id
-            let account_id = extract_claim(&body).map_err(|e| e.encode())?;
-            let quote = GistQuote {
-                username: gist_url.username,
+            let account_id = provider
+                .extract_claim(&body, &self.allowed_ss58_prefixes())
+                .map_err(|e| e.encode())?;
+            // Verify the signature of the claim if any
+            match extract_signature(&body).map_err(|e| e.encode())? {
+                Some(signature) => {
+                    let message = claim_message(&proof_url, &account_id);
+                    if !verify_claim_signature(&message, &account_id, &signature) {
+                        return Err(Error::InvalidClaimSignature.encode());
+                    }
+                }
+                None if self.require_signed_claims() => {
+                    return Err(Error::SignatureRequired.encode());
+                }
+                None => {}
+            }
+            let quote = IdentityQuote {
+                provider: proof_url.provider,
+                handle: proof_url.handle,
                 account_id,
             };
             let result = self.attestation_generator.sign(quote);
@@ -180,61 +299,417 @@ mod easy_oracle {
         }
     }
 
-    #[derive(PartialEq, Eq, Debug)]
-    struct GistUrl {
-        username: String,
-        gist_id: String,
-        filename: String,
+    /// The identity proof providers
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Encode, Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum Provider {
+        /// Github Gist, identified by the Github username
+        GithubGist,
+        /// GitLab snippet, identified by the GitLab namespace
+        GitlabSnippet,
+        /// A `/.well-known/` file, identified by the domain
+        WellKnown,
+    }
+
+    impl Provider {
+        fn name(&self) -> &'static str {
+            match self {
+                Provider::GithubGist => "github",
+                Provider::GitlabSnippet => "gitlab",
+                Provider::WellKnown => "web",
+            }
+        }
+    }
+
+    /// A parsed identity proof url
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    struct ProofUrl {
+        provider: Provider,
+        /// The identity handle on the provider
+        handle: String,
+        /// The unique id of the proof, used as the nonce of the signed claims
+        nonce: String,
     }
 
     #[derive(Clone, Encode, Decode, Debug)]
     #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
-    pub struct GistQuote {
-        username: String,
+    pub struct IdentityQuote {
+        provider: Provider,
+        handle: String,
         account_id: AccountId,
     }
 
-    /// Parses a Github Gist url.
+    /// A source of identity proofs
+    ///
+    /// The provider parses the proof url, fetches the proof, and extracts the claim from it.
+    trait IdentityProvider {
+        /// Parses the raw file url of a proof
+        ///
+        /// - Returns a parsed [ProofUrl] if the url belongs to this provider;
+        /// - Otherwise returns [Error::InvalidUrl].
+        fn parse_url(&self, url: &str) -> Result<ProofUrl>;
+
+        /// The statement before the address in the claim
+        fn claim_prefix(&self) -> &'static str;
+
+        /// Confirms the handle in the url is the owner of the proof
+        ///
+        /// `url` is the raw file url already accepted by [`parse_url`](Self::parse_url), and
+        /// `api_token` is the token set by the admin for the provider API if any. By default the
+        /// url itself is trusted.
+        fn verify_owner(
+            &self,
+            _url: &str,
+            _proof_url: &ProofUrl,
+            _api_token: Option<&str>,
+        ) -> Result<()> {
+            Ok(())
+        }
+
+        /// Fetches the content of the proof
+        fn fetch(&self, url: &str) -> Result<Vec<u8>> {
+            let response = http_get!(url);
+            if response.status_code != 200 {
+                return Err(Error::RequestFailed);
+            }
+            Ok(response.body)
+        }
+
+        /// Extracts the owner account from the content of the proof
+        fn extract_claim(&self, body: &[u8], allowed_prefixes: &[u16]) -> Result<AccountId> {
+            extract_claim(self.claim_prefix(), body, allowed_prefixes)
+        }
+    }
+
+    /// Github Gist: `https://gist.githubusercontent.com/<username>/<gist_id>/raw/<revision>/<file>`
+    ///
+    /// The username and the gist id are case-insensitive, and are normalized to lowercase. Since
+    /// anyone can put any username in the raw url, the owner is confirmed with the Github API.
+    struct GithubGist;
+
+    impl IdentityProvider for GithubGist {
+        fn parse_url(&self, url: &str) -> Result<ProofUrl> {
+            let path = url
+                .strip_prefix("https://gist.githubusercontent.com/")
+                .ok_or(Error::InvalidUrl)?;
+            let components: Vec<_> = path.split('/').collect();
+            if components.len() != 5 || components[2] != "raw" {
+                return Err(Error::InvalidUrl);
+            }
+            let (username, gist_id, revision, filename) =
+                (components[0], components[1], components[3], components[4]);
+            if !is_github_username(username)
+                || !is_hex(gist_id)
+                || !(revision.len() == 40 && is_hex(revision))
+                || !is_plain_filename(filename)
+            {
+                return Err(Error::InvalidUrl);
+            }
+            Ok(ProofUrl {
+                provider: Provider::GithubGist,
+                handle: username.to_ascii_lowercase(),
+                nonce: gist_id.to_ascii_lowercase(),
+            })
+        }
+
+        fn claim_prefix(&self) -> &'static str {
+            "This gist is owned by address: "
+        }
+
+        fn verify_owner(
+            &self,
+            _url: &str,
+            proof_url: &ProofUrl,
+            api_token: Option<&str>,
+        ) -> Result<()> {
+            let url = ink_prelude::format!("https://api.github.com/gists/{}", proof_url.nonce);
+            let mut headers = ink_prelude::vec![
+                (
+                    "Accept".to_string(),
+                    "application/vnd.github+json".to_string()
+                ),
+                ("User-Agent".to_string(), "phala-easy-oracle".to_string()),
+            ];
+            if let Some(token) = api_token {
+                headers.push((
+                    "Authorization".to_string(),
+                    ink_prelude::format!("Bearer {}", token),
+                ));
+            }
+            let response = http_get!(url, headers);
+            if response.status_code != 200 {
+                return Err(Error::RequestFailed);
+            }
+            let login = extract_gist_owner(&response.body).ok_or(Error::InvalidGistMetadata)?;
+            if !login.eq_ignore_ascii_case(&proof_url.handle) {
+                return Err(Error::OwnerMismatch);
+            }
+            Ok(())
+        }
+    }
+
+    /// Checks if `name` is a valid Github username (alphanumerics or single hyphens)
+    fn is_github_username(name: &str) -> bool {
+        (1..=39).contains(&name.len())
+            && name.bytes().all(|c| c.is_ascii_alphanumeric() || c == b'-')
+            && !name.starts_with('-')
+            && !name.ends_with('-')
+    }
+
+    /// Checks if `name` is a valid GitLab namespace or project path
+    fn is_gitlab_path(name: &str) -> bool {
+        name.bytes()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, b'-' | b'_' | b'.'))
+            && name.starts_with(|c: char| c.is_ascii_alphanumeric() || c == '_')
+            && !name.ends_with('.')
+    }
+
+    fn is_hex(s: &str) -> bool {
+        !s.is_empty() && s.bytes().all(|c| c.is_ascii_hexdigit())
+    }
+
+    /// Checks if `filename` is a single path segment
+    ///
+    /// Any percent-encoding is rejected, since the server may decode it more than once (e.g.
+    /// `%252e%252e` as `..`).
+    fn is_plain_filename(filename: &str) -> bool {
+        !filename.is_empty()
+            && filename != "."
+            && filename != ".."
+            && !filename.contains(|c| matches!(c, '\\' | '?' | '#' | '%'))
+    }
+
+    /// Extracts `owner.login` from the gist metadata returned by the Github API
+    fn extract_gist_owner(body: &[u8]) -> Option<String> {
+        let body = core::str::from_utf8(body).ok()?;
+        let owner = json_field(body, "owner")?;
+        let login = json_field(owner, "login")?;
+        let login = login.strip_prefix('"')?.strip_suffix('"')?;
+        is_github_username(login).then(|| login.to_string())
+    }
+
+    /// Extracts `author.username` from the snippet metadata returned by the GitLab API
+    fn extract_snippet_author(body: &[u8]) -> Option<String> {
+        let body = core::str::from_utf8(body).ok()?;
+        let author = json_field(body, "author")?;
+        let username = json_field(author, "username")?;
+        let username = username.strip_prefix('"')?.strip_suffix('"')?;
+        is_gitlab_path(username).then(|| username.to_string())
+    }
+
+    /// Returns the raw JSON value of `key` in the top-level object of `json`
+    ///
+    /// A minimal scanner instead of a full JSON parser. The value of each key is skipped as a
+    /// whole, so the keys in the strings or the nested objects are never matched.
+    fn json_field<'a>(json: &'a str, key: &str) -> Option<&'a str> {
+        let mut rest = json.trim_start().strip_prefix('{')?;
+        loop {
+            rest = rest.trim_start();
+            if !rest.starts_with('"') {
+                return None;
+            }
+            let key_end = json_value_len(rest)?;
+            let name = &rest[1..key_end - 1];
+            let value = rest[key_end..].trim_start().strip_prefix(':')?.trim_start();
+            let value_end = json_value_len(value)?;
+            if name == key {
+                return Some(&value[..value_end]);
+            }
+            rest = value[value_end..].trim_start().strip_prefix(',')?;
+        }
+    }
+
+    /// Returns the length of the JSON value at the beginning of `json`
+    ///
+    /// The brackets are matched outside the strings. The scalars end at the next delimiter.
+    fn json_value_len(json: &str) -> Option<usize> {
+        let bytes = json.as_bytes();
+        let mut depth = 0usize;
+        let mut i = 0;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'"' => {
+                    // Skip the string with the escaped characters
+                    i += 1;
+                    while *bytes.get(i)? != b'"' {
+                        i += if bytes[i] == b'\\' { 2 } else { 1 };
+                    }
+                }
+                b'{' | b'[' => depth += 1,
+                b'}' | b']' if depth > 0 => depth -= 1,
+                b',' | b'}' | b']' => return Some(i),
+                c if depth == 0 && c.is_ascii_whitespace() => return Some(i),
+                _ => {}
+            }
+            i += 1;
+            if depth == 0 && matches!(bytes[i - 1], b'"' | b'}' | b']') {
+                return Some(i);
+            }
+        }
+        (depth == 0).then(|| i)
+    }
+
+    /// GitLab snippet: `https://gitlab.com/<namespace>/<project>/-/snippets/<id>/raw[/<ref>/<file>]`
+    ///
+    /// The namespace is case-insensitive, and is normalized to lowercase. Any member of a group
+    /// project can create a snippet in it, so the owner is confirmed by the snippet author with
+    /// the GitLab API. Only the personal namespace of the author can be claimed.
+    struct GitlabSnippet;
+
+    /// The components of a snippet url: the namespace, the project, the snippet id, and the
+    /// optional ref and file
+    type SnippetPath<'a> = (&'a str, &'a str, &'a str, Option<(&'a str, &'a str)>);
+
+    impl GitlabSnippet {
+        fn split_url<'a>(&self, url: &'a str) -> Result<SnippetPath<'a>> {
+            let path = url
+                .strip_prefix("https://gitlab.com/")
+                .ok_or(Error::InvalidUrl)?;
+            let components: Vec<_> = path.split('/').collect();
+            match components[..] {
+                [namespace, project, "-", "snippets", snippet_id, "raw"] => {
+                    Ok((namespace, project, snippet_id, None))
+                }
+                [namespace, project, "-", "snippets", snippet_id, "raw", git_ref, filename] => {
+                    Ok((namespace, project, snippet_id, Some((git_ref, filename))))
+                }
+                _ => Err(Error::InvalidUrl),
+            }
+        }
+    }
+
+    impl IdentityProvider for GitlabSnippet {
+        fn parse_url(&self, url: &str) -> Result<ProofUrl> {
+            let (namespace, project, snippet_id, file) = self.split_url(url)?;
+            if !is_gitlab_path(namespace)
+                || !is_gitlab_path(project)
+                || snippet_id.is_empty()
+                || !snippet_id.bytes().all(|c| c.is_ascii_digit())
+                || !file.map_or(true, |(git_ref, filename)| {
+                    is_plain_filename(git_ref) && is_plain_filename(filename)
+                })
+            {
+                return Err(Error::InvalidUrl);
+            }
+            Ok(ProofUrl {
+                provider: Provider::GitlabSnippet,
+                handle: namespace.to_ascii_lowercase(),
+                nonce: snippet_id.to_string(),
+            })
+        }
+
+        fn claim_prefix(&self) -> &'static str {
+            "This snippet is owned by address: "
+        }
+
+        fn verify_owner(
+            &self,
+            url: &str,
+            proof_url: &ProofUrl,
+            api_token: Option<&str>,
+        ) -> Result<()> {
+            let (namespace, project, snippet_id, _) = self.split_url(url)?;
+            let url = ink_prelude::format!(
+                "https://gitlab.com/api/v4/projects/{}%2F{}/snippets/{}",
+                namespace,
+                project,
+                snippet_id
+            );
+            let mut headers =
+                ink_prelude::vec![("User-Agent".to_string(), "phala-easy-oracle".to_string())];
+            if let Some(token) = api_token {
+                headers.push(("PRIVATE-TOKEN".to_string(), token.to_string()));
+            }
+            let response = http_get!(url, headers);
+            if response.status_code != 200 {
+                return Err(Error::RequestFailed);
+            }
+            let author =
+                extract_snippet_author(&response.body).ok_or(Error::InvalidSnippetMetadata)?;
+            if !author.eq_ignore_ascii_case(&proof_url.handle) {
+                return Err(Error::OwnerMismatch);
+            }
+            Ok(())
+        }
+    }
+
+    /// A `/.well-known/` file on a domain: `https://<domain>/.well-known/<file>`
+    struct WellKnown;
+
+    impl IdentityProvider for WellKnown {
+        fn parse_url(&self, url: &str) -> Result<ProofUrl> {
+            let path = url.strip_prefix("https://").ok_or(Error::InvalidUrl)?;
+            let (domain, file) = path.split_once("/.well-known/").ok_or(Error::InvalidUrl)?;
+            // Only a bare host is accepted (no path, credentials or port), and the file must stay
+            // under `/.well-known/`
+            if domain.is_empty()
+                || domain.contains(|c| matches!(c, '/' | '\\' | '@' | ':' | '?' | '#' | '%'))
+                || !file.split('/').all(is_plain_filename)
+            {
+                return Err(Error::InvalidUrl);
+            }
+            let domain = domain.to_ascii_lowercase();
+            Ok(ProofUrl {
+                provider: Provider::WellKnown,
+                handle: domain.clone(),
+                nonce: domain,
+            })
+        }
+
+        fn claim_prefix(&self) -> &'static str {
+            "This domain is owned by address: "
+        }
+    }
+
+    /// The supported providers, in the order to match the proof url
+    const PROVIDERS: [&dyn IdentityProvider; 3] = [&GithubGist, &GitlabSnippet, &WellKnown];
+
+    /// Parses an identity proof url with the first provider accepting it.
     ///
-    /// - Returns a parsed [GistUrl] struct if the input is a valid url;
+    /// - Returns the provider and the parsed [ProofUrl] if the input is a valid url;
     /// - Otherwise returns an [Error].
-    fn parse_gist_url(url: &str) -> Result<GistUrl> {
-        let path = url
-            .strip_prefix("https://gist.githubusercontent.com/")
-            .ok_or(Error::InvalidUrl)?;
-        let components: Vec<_> = path.split('/').collect();
-        if components.len() < 5 {
-            return Err(Error::InvalidUrl);
-        }
-        Ok(GistUrl {
-            username: components[0].to_string(),
-            gist_id: components[1].to_string(),
-            filename: components[4].to_string(),
-        })
+    fn parse_proof_url(url: &str) -> Result<(&'static dyn IdentityProvider, ProofUrl)> {
+        PROVIDERS
+            .iter()
+            .find_map(|provider| Some((*provider, provider.parse_url(url).ok()?)))
+            .ok_or(Error::InvalidUrl)
     }
 
-    const CLAIM_PREFIX: &str = "This gist is owned by address: 0x";
+    const HEX_PREFIX: &str = "0x";
     const ADDRESS_LEN: usize = 64;
 
-    /// Extracts the ownerhip of the gist from a claim in the gist body.
+    /// The SS58 prefixes accepted by default: Polkadot, Kusama, Phala and the generic Substrate
+    const DEFAULT_SS58_PREFIXES: [u16; 4] = [0, 2, 30, 42];
+    const BASE58_ALPHABET: &[u8; 58] =
+        b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+    const SS58_CHECKSUM_PREFIX: &[u8] = b"SS58PRE";
+    const SS58_CHECKSUM_LEN: usize = 2;
+    /// The max length of an SS58 address, with a two-byte prefix, in base58
+    const SS58_MAX_LEN: usize = 50;
+
+    /// Extracts the ownerhip of the proof from a claim in the proof body.
     ///
-    /// A valid claim must have the statement "This gist is owned by address: 0x..." in `body`. The
-    /// address must be the 256 bits public key of the Substrate account in hex.
+    /// A valid claim must have the statement `prefix` (e.g. "This gist is owned by address: ")
+    /// followed by the address in `body`. The address must be either the 256 bits public key of
+    /// the Substrate account in hex with the "0x" prefix, or an SS58 address with one of the
+    /// `allowed_prefixes`.
     ///
     /// - Returns a 256-bit `AccountId` representing the owner account if the claim is valid;
     /// - otherwise returns an [Error].
-    fn extract_claim(body: &[u8]) -> Result<AccountId> {
+    fn extract_claim(prefix: &str, body: &[u8], allowed_prefixes: &[u16]) -> Result<AccountId> {
         let body = String::from_utf8_lossy(body);
-        let pos = body.find(CLAIM_PREFIX).ok_or(Error::NoClaimFound)?;
-        let addr: String = body
-            .chars()
-            .skip(pos)
-            .skip(CLAIM_PREFIX.len())
-            .take(ADDRESS_LEN)
+        let pos = body.find(prefix).ok_or(Error::NoClaimFound)?;
+        let claim = &body[pos + prefix.len()..];
+        if let Some(hex) = claim.strip_prefix(HEX_PREFIX) {
+            let addr: String = hex.chars().take(ADDRESS_LEN).collect();
+            return decode_accountid_256(addr.as_bytes());
+        }
+        let addr: Vec<u8> = claim
+            .bytes()
+            .take_while(|c| BASE58_ALPHABET.contains(c))
+            .take(SS58_MAX_LEN)
             .collect();
-        let addr = addr.as_bytes();
-        let account_id = decode_accountid_256(addr)?;
-        Ok(account_id)
+        decode_ss58(&addr, allowed_prefixes)
     }
 
     /// Decodes a hex string as an 256-bit AccountId32
@@ -247,6 +722,117 @@ mod easy_oracle {
         Ok(AccountId::from(bytes))
     }
 
+    const SIGNATURE_PREFIX: &str = "Signature: 0x";
+    const SIGNATURE_LEN: usize = 128;
+
+    /// Extracts the signature of the claim from the gist body if exists.
+    ///
+    /// The signature is the statement "Signature: 0x..." with a 512-bit sr25519 or ed25519
+    /// signature in hex.
+    fn extract_signature(body: &[u8]) -> Result<Option<[u8; 64]>> {
+        use hex::FromHex;
+        let body = String::from_utf8_lossy(body);
+        let pos = match body.find(SIGNATURE_PREFIX) {
+            Some(pos) => pos,
+            None => return Ok(None),
+        };
+        let sig: String = body[pos + SIGNATURE_PREFIX.len()..]
+            .chars()
+            .take(SIGNATURE_LEN)
+            .collect();
+        if sig.len() != SIGNATURE_LEN {
+            return Err(Error::InvalidClaimSignature);
+        }
+        let bytes = <[u8; 64]>::from_hex(sig).or(Err(Error::InvalidClaimSignature))?;
+        Ok(Some(bytes))
+    }
+
+    /// Returns the canonical message to sign by the claimed account
+    ///
+    /// The proof id (e.g. the gist id) works as the nonce, so a signature can't be reused in
+    /// other proofs. Both the handle and the proof id are the normalized ones in `proof_url`.
+    fn claim_message(proof_url: &ProofUrl, account_id: &AccountId) -> String {
+        let account: &[u8; 32] = account_id.as_ref();
+        ink_prelude::format!(
+            "Proof {}:{}/{} is owned by address: 0x{}",
+            proof_url.provider.name(),
+            proof_url.handle,
+            proof_url.nonce,
+            hex::encode(account)
+        )
+    }
+
+    /// Verifies the signature of a claim message by the claimed account
+    ///
+    /// Accepts both sr25519 and ed25519 signatures, over either the raw message or the message
+    /// wrapped in `<Bytes>...</Bytes>` as signed by the Polkadot.js extension.
+    fn verify_claim_signature(message: &str, account_id: &AccountId, signature: &[u8; 64]) -> bool {
+        use pink::chain_extension::SigType;
+        let pubkey: &[u8; 32] = account_id.as_ref();
+        let wrapped = ink_prelude::format!("<Bytes>{}</Bytes>", message);
+        [message, wrapped.as_str()].iter().any(|message| {
+            [SigType::Sr25519, SigType::Ed25519]
+                .into_iter()
+                .any(|sig_type| pink::ext().verify(sig_type, pubkey, message.as_bytes(), signature))
+        })
+    }
+
+    /// Decodes an SS58 address as an 256-bit AccountId32
+    ///
+    /// The network prefix must be one of `allowed_prefixes`. Both the one-byte and two-byte
+    /// prefixes are supported.
+    fn decode_ss58(addr: &[u8], allowed_prefixes: &[u16]) -> Result<AccountId> {
+        let data = decode_base58(addr).ok_or(Error::InvalidAddress)?;
+        let (prefix_len, prefix) = match data.first().copied() {
+            Some(0..=63) => (1, data[0] as u16),
+            Some(64..=127) if data.len() > 1 => {
+                let lower = (data[0] << 2) | (data[1] >> 6);
+                let upper = data[1] & 0b0011_1111;
+                (2, (lower as u16) | ((upper as u16) << 8))
+            }
+            _ => return Err(Error::InvalidAddress),
+        };
+        if data.len() != prefix_len + 32 + SS58_CHECKSUM_LEN {
+            return Err(Error::InvalidAddressLength);
+        }
+        let (payload, checksum) = data.split_at(prefix_len + 32);
+        let hash = blake2_rfc::blake2b::blake2b(64, &[], &[SS58_CHECKSUM_PREFIX, payload].concat());
+        if hash.as_bytes()[..SS58_CHECKSUM_LEN] != *checksum {
+            return Err(Error::BadChecksum);
+        }
+        if !allowed_prefixes.contains(&prefix) {
+            return Err(Error::WrongNetwork);
+        }
+        let mut bytes = [0u8; 32];
+        bytes.copy_from_slice(&payload[prefix_len..]);
+        Ok(AccountId::from(bytes))
+    }
+
+    /// Decodes a base58 string (in the Bitcoin alphabet)
+    ///
+    /// Returns `None` if there's any invalid character.
+    fn decode_base58(input: &[u8]) -> Option<Vec<u8>> {
+        // The big number in little endian
+        let mut bytes: Vec<u8> = Vec::new();
+        for c in input {
+            let mut carry = BASE58_ALPHABET.iter().position(|a| a == c)? as u32;
+            for byte in bytes.iter_mut() {
+                carry += (*byte as u32) * 58;
+                *byte = carry as u8;
+                carry >>= 8;
+            }
+            while carry > 0 {
+                bytes.push(carry as u8);
+                carry >>= 8;
+            }
+        }
+        // Each leading '1' is a leading zero byte
+        let zeros = input.iter().take_while(|c| **c == b'1').count();
+        bytes.extend(core::iter::repeat(0).take(zeros));
+        bytes.reverse();
+        Some(bytes)
+    }
+
     #[cfg(test)]
     mod tests {
         use super::*;
@@ -256,24 +842,409 @@ mod easy_oracle {
             ink_env::test::default_accounts::<Environment>()
         }
 
+        const GIST_URL: &str = "https://gist.githubusercontent.com/h4x3rotab/0cabeb528bdaf30e4cf741e26b714e04/raw/620f958fb92baba585a77c1854d68dc986803b4e/test-gist";
+
+        /// Mocks a gist with the `body` owned by `owner` in the Github API
+        fn mock_gist(owner: &'static str, body: &'static [u8]) {
+            use pink_extension::chain_extension::{mock, HttpResponse};
+            mock::mock_http_request(move |request| {
+                if request.url.starts_with("https://api.github.com/gists/") {
+                    let metadata = format!(
+                        r#"{{"id": "0cabeb528bdaf30e4cf741e26b714e04", "owner": {{"login": "{}", "id": 1}}, "history": []}}"#,
+                        owner
+                    );
+                    HttpResponse::ok(metadata.into_bytes())
+                } else {
+                    HttpResponse::ok(body.to_vec())
+                }
+            });
+        }
+
+        const SNIPPET_API_URL: &str = "https://gitlab.com/api/v4/projects/";
+
+        /// Mocks a snippet with the `body` authored by `author` in the GitLab API
+        fn mock_snippet(author: &'static str, body: &'static [u8]) {
+            use pink_extension::chain_extension::{mock, HttpResponse};
+            mock::mock_http_request(move |request| {
+                if request.url.starts_with(SNIPPET_API_URL) {
+                    let metadata = format!(
+                        r#"{{"id": 2345678, "title": "proof", "author": {{"id": 1, "username": "{}"}}}}"#,
+                        author
+                    );
+                    HttpResponse::ok(metadata.into_bytes())
+                } else {
+                    HttpResponse::ok(body.to_vec())
+                }
+            });
+        }
+
         #[ink::test]
-        fn can_parse_gist_url() {
-            let result = parse_gist_url("https://gist.githubusercontent.com/h4x3rotab/0cabeb528bdaf30e4cf741e26b714e04/raw/620f958fb92baba585a77c1854d68dc986803b4e/test%2520gist");
-            assert_eq!(
-                result,
-                Ok(GistUrl {
-                    username: "h4x3rotab".to_string(),
-                    gist_id: "0cabeb528bdaf30e4cf741e26b714e04".to_string(),
-                    filename: "test%2520gist".to_string(),
+        fn can_parse_proof_urls() {
+            let parse = |url| parse_proof_url(url).map(|(_, proof_url)| proof_url);
+            assert_eq!(
+                parse(GIST_URL),
+                Ok(ProofUrl {
+                    provider: Provider::GithubGist,
+                    handle: "h4x3rotab".to_string(),
+                    nonce: "0cabeb528bdaf30e4cf741e26b714e04".to_string(),
                 })
             );
-            let err = parse_gist_url("http://example.com");
-            assert_eq!(err, Err(Error::InvalidUrl));
+            assert_eq!(
+                parse("https://gitlab.com/h4x3rotab/proofs/-/snippets/2345678/raw/main/proof.txt"),
+                Ok(ProofUrl {
+                    provider: Provider::GitlabSnippet,
+                    handle: "h4x3rotab".to_string(),
+                    nonce: "2345678".to_string(),
+                })
+            );
+            assert_eq!(
+                parse("https://Phala.Network/.well-known/poap.txt"),
+                Ok(ProofUrl {
+                    provider: Provider::WellKnown,
+                    handle: "phala.network".to_string(),
+                    nonce: "phala.network".to_string(),
+                })
+            );
+            // Bad cases
+            assert_eq!(parse("http://example.com"), Err(Error::InvalidUrl));
+            assert_eq!(
+                parse("https://gitlab.com/h4x3rotab/proofs/-/issues/1/raw"),
+                Err(Error::InvalidUrl)
+            );
+            assert_eq!(
+                parse("http://phala.network/.well-known/poap.txt"),
+                Err(Error::InvalidUrl)
+            );
+            assert_eq!(
+                parse("https://evil.com@phala.network/.well-known/poap.txt"),
+                Err(Error::InvalidUrl)
+            );
+            assert_eq!(
+                parse("https://evil.com/phala.network/.well-known/poap.txt"),
+                Err(Error::InvalidUrl)
+            );
+            assert_eq!(
+                parse("https://phala.network/.well-known/"),
+                Err(Error::InvalidUrl)
+            );
+            // Traversal tricks
+            for url in [
+                "https://github.com/.well-known/../attacker/repo/raw/main/proof",
+                "https://github.com/.well-known/%2e%2e/attacker/repo/raw/main/proof",
+                "https://github.com/.well-known/proofs/../../attacker/proof",
+                "https://github.com/.well-known/proofs//poap.txt",
+                "https://github.com/.well-known/poap.txt?x=../attacker",
+                "https://evil.com\\@phala.network/.well-known/poap.txt",
+                "https://evil.com%2fphala.network/.well-known/poap.txt",
+            ] {
+                assert_eq!(parse(url), Err(Error::InvalidUrl), "{}", url);
+            }
+            // Nested files are allowed
+            assert!(parse("https://phala.network/.well-known/proofs/poap.txt").is_ok());
+        }
+
+        #[ink::test]
+        fn rejects_spoofed_gists() {
+            let parse = |url| GithubGist.parse_url(url);
+            // Case is normalized
+            assert_eq!(
+                parse("https://gist.githubusercontent.com/H4x3rotab/0CABEB528bdaf30e4cf741e26b714e04/raw/620F958fb92baba585a77c1854d68dc986803b4e/test-gist"),
+                parse(GIST_URL)
+            );
+            // Malformed urls
+            for url in [
+                // Bad username
+                "https://gist.githubusercontent.com/-h4x3rotab/0cabeb528bdaf30e4cf741e26b714e04/raw/620f958fb92baba585a77c1854d68dc986803b4e/test",
+                "https://gist.githubusercontent.com/h4x3%72otab/0cabeb528bdaf30e4cf741e26b714e04/raw/620f958fb92baba585a77c1854d68dc986803b4e/test",
+                // Gist id not in hex
+                "https://gist.githubusercontent.com/h4x3rotab/0cabeb528bdaf30e4cf741e26b714e0z/raw/620f958fb92baba585a77c1854d68dc986803b4e/test",
+                "https://gist.githubusercontent.com/h4x3rotab/../raw/620f958fb92baba585a77c1854d68dc986803b4e/test",
+                // Bad revision
+                "https://gist.githubusercontent.com/h4x3rotab/0cabeb528bdaf30e4cf741e26b714e04/raw/620f958/test",
+                "https://gist.githubusercontent.com/h4x3rotab/0cabeb528bdaf30e4cf741e26b714e04/blob/620f958fb92baba585a77c1854d68dc986803b4e/test",
+                // Traversal tricks
+                "https://gist.githubusercontent.com/h4x3rotab/0cabeb528bdaf30e4cf741e26b714e04/raw/620f958fb92baba585a77c1854d68dc986803b4e/..",
+                "https://gist.githubusercontent.com/h4x3rotab/0cabeb528bdaf30e4cf741e26b714e04/raw/620f958fb92baba585a77c1854d68dc986803b4e/%2e%2E",
+                "https://gist.githubusercontent.com/h4x3rotab/0cabeb528bdaf30e4cf741e26b714e04/raw/620f958fb92baba585a77c1854d68dc986803b4e/..%2F..%2Fevil%2Fgist",
+                "https://gist.githubusercontent.com/h4x3rotab/0cabeb528bdaf30e4cf741e26b714e04/raw/620f958fb92baba585a77c1854d68dc986803b4e/a%5c..%5cb",
+                "https://gist.githubusercontent.com/h4x3rotab/0cabeb528bdaf30e4cf741e26b714e04/raw/620f958fb92baba585a77c1854d68dc986803b4e/%252e%252e%252f%252e%252e%252fevil",
+                "https://gist.githubusercontent.com/h4x3rotab/0cabeb528bdaf30e4cf741e26b714e04/raw/620f958fb92baba585a77c1854d68dc986803b4e/test%2520gist",
+                "https://gist.githubusercontent.com/h4x3rotab/0cabeb528bdaf30e4cf741e26b714e04/raw/620f958fb92baba585a77c1854d68dc986803b4e/../../evil/gist",
+                "https://gist.githubusercontent.com/h4x3rotab/0cabeb528bdaf30e4cf741e26b714e04/raw/620f958fb92baba585a77c1854d68dc986803b4e/test?x=../evil",
+            ] {
+                assert_eq!(parse(url), Err(Error::InvalidUrl), "{}", url);
+            }
+
+            // Owner metadata
+            assert_eq!(
+                extract_gist_owner(br#"{"owner": {"login": "h4x3rotab", "id": 1}}"#),
+                Some("h4x3rotab".to_string())
+            );
+            assert_eq!(
+                extract_gist_owner(
+                    br#"{"files": {"a": {"content": "\"owner\": {\"login\": \"evil\"}"}}, "owner": {"id": 1, "login": "h4x3rotab"}}"#
+                ),
+                Some("h4x3rotab".to_string())
+            );
+            // Only the top-level owner counts
+            assert_eq!(
+                extract_gist_owner(
+                    br#"{"files": {"owner": {"filename": "owner", "owner": {"login": "evil"}}}, "owner": {"user": {"login": "evil"}, "login": "h4x3rotab", "tags": ["}", "login"]}}"#
+                ),
+                Some("h4x3rotab".to_string())
+            );
+            assert_eq!(
+                extract_gist_owner(br#"{"files": {"owner": {"owner": {"login": "evil"}}}}"#),
+                None
+            );
+            assert_eq!(
+                extract_gist_owner(br#"{"description": "\"owner\": {\"login\": \"evil\"}"}"#),
+                None
+            );
+            assert_eq!(extract_gist_owner(br#"{"owner": null}"#), None);
+            assert_eq!(
+                extract_gist_owner(br#"{"owner": {"id": 1}, "user": {"login": "evil"}}"#),
+                None
+            );
+
+            // Verified by the Github API
+            use pink_extension::chain_extension::{mock, HttpResponse};
+            pink_extension_runtime::mock_ext::mock_all_ext();
+            let contract = EasyOracle::new();
+            let claim: &[u8] = b"This gist is owned by address: 0x0101010101010101010101010101010101010101010101010101010101010101";
+            mock_gist("H4X3ROTAB", claim);
+            assert!(contract.attest(GIST_URL.to_string()).is_ok());
+            mock_gist("someone", claim);
+            assert_eq!(
+                contract.attest(GIST_URL.to_string()).err(),
+                Some(Error::OwnerMismatch.encode())
+            );
+            mock::mock_http_request(|request| {
+                if request.url.starts_with("https://api.github.com/") {
+                    HttpResponse::not_found()
+                } else {
+                    HttpResponse::ok(b"This gist is owned by address: 0x0101010101010101010101010101010101010101010101010101010101010101".to_vec())
+                }
+            });
+            assert_eq!(
+                contract.attest(GIST_URL.to_string()).err(),
+                Some(Error::RequestFailed.encode())
+            );
+            mock::mock_http_request(|_| {
+                HttpResponse::ok(b"This gist is owned by address: 0x0101010101010101010101010101010101010101010101010101010101010101".to_vec())
+            });
+            assert_eq!(
+                contract.attest(GIST_URL.to_string()).err(),
+                Some(Error::InvalidGistMetadata.encode())
+            );
+        }
+
+        #[ink::test]
+        fn rejects_malformed_snippets() {
+            let parse = |url| GitlabSnippet.parse_url(url);
+            // Case is normalized, and the ref and file are optional
+            let snippet = parse("https://gitlab.com/h4x3rotab/proofs/-/snippets/2345678/raw");
+            assert_eq!(
+                snippet,
+                Ok(ProofUrl {
+                    provider: Provider::GitlabSnippet,
+                    handle: "h4x3rotab".to_string(),
+                    nonce: "2345678".to_string(),
+                })
+            );
+            assert_eq!(
+                parse("https://gitlab.com/H4x3Rotab/proofs/-/snippets/2345678/raw/main/proof.txt"),
+                snippet
+            );
+            assert!(parse("https://gitlab.com/_h4x3.rotab/my-proofs_1/-/snippets/1/raw").is_ok());
+            // Malformed urls
+            for url in [
+                // Bad namespace or project
+                "https://gitlab.com/-h4x3rotab/proofs/-/snippets/2345678/raw",
+                "https://gitlab.com/h4x3rotab./proofs/-/snippets/2345678/raw",
+                "https://gitlab.com/h4x3%72otab/proofs/-/snippets/2345678/raw",
+                "https://gitlab.com/h4x3rotab/../-/snippets/2345678/raw",
+                "https://gitlab.com//proofs/-/snippets/2345678/raw",
+                // Bad snippet id
+                "https://gitlab.com/h4x3rotab/proofs/-/snippets/23456x8/raw",
+                "https://gitlab.com/h4x3rotab/proofs/-/snippets//raw",
+                "https://gitlab.com/h4x3rotab/proofs/-/snippets/2345678/raw/main",
+                "https://gitlab.com/h4x3rotab/proofs/-/snippets/2345678/blob/main/proof.txt",
+                // Traversal tricks
+                "https://gitlab.com/h4x3rotab/proofs/-/snippets/1/raw/../../../../../attacker/proofs/-/snippets/2/raw/main/proof.txt",
+                "https://gitlab.com/h4x3rotab/proofs/-/snippets/1/raw/main/..",
+                "https://gitlab.com/h4x3rotab/proofs/-/snippets/1/raw/./proof.txt",
+                "https://gitlab.com/h4x3rotab/proofs/-/snippets/1/raw/%2e%2E/proof.txt",
+                "https://gitlab.com/h4x3rotab/proofs/-/snippets/1/raw/main/..%2F..%2Fattacker",
+                "https://gitlab.com/h4x3rotab/proofs/-/snippets/1/raw/main/a%5c..%5cb",
+                "https://gitlab.com/h4x3rotab/proofs/-/snippets/1/raw/%252e%252e/%252e%252e%252fattacker",
+                "https://gitlab.com/h4x3rotab/proofs/-/snippets/1/raw/main/proof%20file.txt",
+                "https://gitlab.com/h4x3rotab/proofs/-/snippets/1/raw/main/proof.txt?x=../attacker",
+            ] {
+                assert_eq!(parse(url), Err(Error::InvalidUrl), "{}", url);
+            }
+
+            // Author metadata
+            assert_eq!(
+                extract_snippet_author(br#"{"author": {"id": 1, "username": "h4x3rotab"}}"#),
+                Some("h4x3rotab".to_string())
+            );
+            assert_eq!(
+                extract_snippet_author(
+                    br#"{"title": "\"author\": {\"username\": \"evil\"}", "author": {"username": "h4x3rotab", "name": "evil"}}"#
+                ),
+                Some("h4x3rotab".to_string())
+            );
+            assert_eq!(
+                extract_snippet_author(br#"{"project": {"author": {"username": "evil"}}}"#),
+                None
+            );
+            assert_eq!(
+                extract_snippet_author(br#"{"author": {"username": ""}}"#),
+                None
+            );
+            assert_eq!(
+                extract_snippet_author(br#"{"author": {"username": "../evil"}}"#),
+                None
+            );
+
+            // The normalized namespace is attested, with the snippet fetched as is
+            use pink_extension::chain_extension::{mock, HttpResponse};
+            pink_extension_runtime::mock_ext::mock_all_ext();
+            let mut contract = EasyOracle::new();
+            mock::mock_http_request(|request| {
+                if request.url
+                    == "https://gitlab.com/api/v4/projects/H4x3Rotab%2Fproofs/snippets/2345678"
+                {
+                    HttpResponse::ok(br#"{"author": {"username": "h4x3rotab"}}"#.to_vec())
+                } else if request.url
+                    == "https://gitlab.com/H4x3Rotab/proofs/-/snippets/2345678/raw/main/proof.txt"
+                {
+                    HttpResponse::ok(b"This snippet is owned by address: 0x0101010101010101010101010101010101010101010101010101010101010101".to_vec())
+                } else {
+                    HttpResponse::not_found()
+                }
+            });
+            let attestation = contract
+                .attest(
+                    "https://gitlab.com/H4x3Rotab/proofs/-/snippets/2345678/raw/main/proof.txt"
+                        .to_string(),
+                )
+                .unwrap();
+            let data: IdentityQuote = Decode::decode(&mut &attestation.data[..]).unwrap();
+            assert_eq!(data.provider, Provider::GitlabSnippet);
+            assert_eq!(data.handle, "h4x3rotab");
+            assert_eq!(
+                contract.attest(
+                    "https://gitlab.com/h4x3rotab/proofs/-/snippets/1/raw/../../../../../attacker/proofs/-/snippets/2/raw/main/proof.txt"
+                        .to_string()
+                ).err(),
+                Some(Error::InvalidUrl.encode())
+            );
+
+            // Verified by the GitLab API
+            let url = "https://gitlab.com/phala/proofs/-/snippets/2345678/raw";
+            let claim: &[u8] = b"This snippet is owned by address: 0x0101010101010101010101010101010101010101010101010101010101010101";
+            mock_snippet("Phala", claim);
+            assert!(contract.attest(url.to_string()).is_ok());
+            // A group member can't claim the group
+            mock_snippet("h4x3rotab", claim);
+            assert_eq!(
+                contract.attest(url.to_string()).err(),
+                Some(Error::OwnerMismatch.encode())
+            );
+            mock::mock_http_request(|request| {
+                if request.url.starts_with(SNIPPET_API_URL) {
+                    HttpResponse::not_found()
+                } else {
+                    HttpResponse::ok(b"This snippet is owned by address: 0x0101010101010101010101010101010101010101010101010101010101010101".to_vec())
+                }
+            });
+            assert_eq!(
+                contract.attest(url.to_string()).err(),
+                Some(Error::RequestFailed.encode())
+            );
+            mock::mock_http_request(|_| {
+                HttpResponse::ok(b"This snippet is owned by address: 0x0101010101010101010101010101010101010101010101010101010101010101".to_vec())
+            });
+            assert_eq!(
+                contract.attest(url.to_string()).err(),
+                Some(Error::InvalidSnippetMetadata.encode())
+            );
+            // The private token is sent to the GitLab API
+            mock::mock_http_request(|request| {
+                if !request.url.starts_with(SNIPPET_API_URL) {
+                    HttpResponse::ok(b"This snippet is owned by address: 0x0101010101010101010101010101010101010101010101010101010101010101".to_vec())
+                } else if request
+                    .headers
+                    .iter()
+                    .any(|(name, value)| name == "PRIVATE-TOKEN" && value == "secret")
+                {
+                    HttpResponse::ok(br#"{"author": {"username": "phala"}}"#.to_vec())
+                } else {
+                    HttpResponse::not_found()
+                }
+            });
+            assert!(contract
+                .set_api_token(Provider::GitlabSnippet, Some("secret".to_string()))
+                .is_ok());
+            assert!(contract.attest(url.to_string()).is_ok());
+        }
+
+        #[ink::test]
+        fn can_attest_all_providers() {
+            use pink_extension::chain_extension::{mock, HttpResponse};
+            pink_extension_runtime::mock_ext::mock_all_ext();
+            let contract = EasyOracle::new();
+            let accounts = default_accounts();
+
+            mock::mock_http_request(|request| {
+                let body: &[u8] = if request.url.starts_with(SNIPPET_API_URL) {
+                    br#"{"author": {"username": "h4x3rotab"}}"#
+                } else if request.url.starts_with("https://gitlab.com/") {
+                    b"This snippet is owned by address: 0x0101010101010101010101010101010101010101010101010101010101010101"
+                } else {
+                    b"This domain is owned by address: 0x0101010101010101010101010101010101010101010101010101010101010101"
+                };
+                HttpResponse::ok(body.to_vec())
+            });
+            for (url, provider, handle) in [
+                (
+                    "https://gitlab.com/h4x3rotab/proofs/-/snippets/2345678/raw/main/proof.txt",
+                    Provider::GitlabSnippet,
+                    "h4x3rotab",
+                ),
+                (
+                    "https://phala.network/.well-known/poap.txt",
+                    Provider::WellKnown,
+                    "phala.network",
+                ),
+            ] {
+                let attestation = contract.attest(url.to_string()).unwrap();
+                let data: IdentityQuote = Decode::decode(&mut &attestation.data[..]).unwrap();
+                assert_eq!(data.provider, provider);
+                assert_eq!(data.handle, handle);
+                assert_eq!(data.account_id, accounts.alice);
+            }
+
+            // The claim statement must match the provider
+            mock::mock_http_request(|_| {
+                HttpResponse::ok(b"This gist is owned by address: 0x0101010101010101010101010101010101010101010101010101010101010101".to_vec())
+            });
+            let result = contract.attest("https://phala.network/.well-known/poap.txt".to_string());
+            assert_eq!(result.err(), Some(Error::NoClaimFound.encode()));
+            // Failed requests
+            mock::mock_http_request(|_| HttpResponse::not_found());
+            let result = contract.attest(
+                "https://gitlab.com/h4x3rotab/proofs/-/snippets/2345678/raw/main/proof.txt"
+                    .to_string(),
+            );
+            assert_eq!(result.err(), Some(Error::RequestFailed.encode()));
         }
 
         #[ink::test]
         fn can_decode_claim() {
-            let ok = extract_claim(b"...This gist is owned by address: 0x0123456789012345678901234567890123456789012345678901234567890123...");
+            let ok = GithubGist.extract_claim(b"...This gist is owned by address: 0x0123456789012345678901234567890123456789012345678901234567890123...", &DEFAULT_SS58_PREFIXES);
             assert_eq!(
                 ok,
                 decode_accountid_256(
@@ -282,23 +1253,284 @@ mod easy_oracle {
             );
             // Bad cases
             assert_eq!(
-                extract_claim(b"This gist is owned by"),
+                GithubGist.extract_claim(b"This gist is owned by", &DEFAULT_SS58_PREFIXES),
                 Err(Error::NoClaimFound),
             );
             assert_eq!(
-                extract_claim(b"This gist is owned by address: 0xAB"),
+                GithubGist.extract_claim(
+                    b"This gist is owned by address: 0xAB",
+                    &DEFAULT_SS58_PREFIXES
+                ),
                 Err(Error::InvalidAddressLength),
             );
             assert_eq!(
-                extract_claim(b"This gist is owned by address: 0xXX23456789012345678901234567890123456789012345678901234567890123"),
+                GithubGist.extract_claim(b"This gist is owned by address: 0xXX23456789012345678901234567890123456789012345678901234567890123", &DEFAULT_SS58_PREFIXES),
                 Err(Error::InvalidAddress),
             );
         }
 
         #[ink::test]
-        fn end_to_end() {
+        fn can_decode_ss58_claim() {
+            // Alice in hex, the generic Substrate format (42), and Polkadot (0)
+            let alice = decode_accountid_256(
+                b"d43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d",
+            );
+            assert!(alice.is_ok());
+            assert_eq!(
+                GithubGist.extract_claim(
+                    b"This gist is owned by address: 0xd43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d",
+                    &DEFAULT_SS58_PREFIXES
+                ),
+                alice
+            );
+            assert_eq!(
+                GithubGist.extract_claim(
+                    b"...This gist is owned by address: 5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY\n...",
+                    &DEFAULT_SS58_PREFIXES
+                ),
+                alice
+            );
+            assert_eq!(
+                GithubGist.extract_claim(
+                    b"This gist is owned by address: 15oF4uVJwmo4TdGW7VfQxNLavjCXviqxT9S1MgbjMNHr6Sp5",
+                    &DEFAULT_SS58_PREFIXES
+                ),
+                alice
+            );
+            // A two-byte prefix (1284)
+            assert_eq!(
+                GithubGist.extract_claim(
+                    b"This gist is owned by address: VdvKmYJfD4VXA9fzz1SbmCo2eYHSzUFbaDCZSuaNKJAe8YNg6",
+                    &[1284]
+                ),
+                alice
+            );
+
+            // Bad cases
+            assert_eq!(
+                GithubGist.extract_claim(
+                    b"This gist is owned by address: 15oF4uVJwmo4TdGW7VfQxNLavjCXviqxT9S1MgbjMNHr6Sp5",
+                    &[42]
+                ),
+                Err(Error::WrongNetwork),
+            );
+            assert_eq!(
+                GithubGist.extract_claim(
+                    b"This gist is owned by address: 5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQZ",
+                    &DEFAULT_SS58_PREFIXES
+                ),
+                Err(Error::BadChecksum),
+            );
+            assert_eq!(
+                GithubGist.extract_claim(
+                    b"This gist is owned by address: 5GrwvaEF5zXb26Fz9rcQ",
+                    &DEFAULT_SS58_PREFIXES
+                ),
+                Err(Error::InvalidAddressLength),
+            );
+            // Only the length of an address is decoded from a long base58 string
+            let long_claim = [
+                b"This gist is owned by address: 5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"
+                    .as_slice(),
+                &[b'z'; 100_000],
+            ]
+            .concat();
+            assert_eq!(
+                GithubGist.extract_claim(&long_claim, &DEFAULT_SS58_PREFIXES),
+                Err(Error::InvalidAddressLength),
+            );
+            assert_eq!(
+                GithubGist.extract_claim(
+                    b"This gist is owned by address: ???",
+                    &DEFAULT_SS58_PREFIXES
+                ),
+                Err(Error::InvalidAddress),
+            );
+        }
+
+        #[ink::test]
+        fn can_verify_signed_claim() {
+            use pink::chain_extension::SigType;
+            pink_extension_runtime::mock_ext::mock_all_ext();
+
+            let proof_url = ProofUrl {
+                provider: Provider::GithubGist,
+                handle: "h4x3rotab".to_string(),
+                nonce: "0cabeb528bdaf30e4cf741e26b714e04".to_string(),
+            };
+            for (sig_type, privkey) in [
+                (
+                    SigType::Sr25519,
+                    pink::ext().derive_sr25519_key(b"alice"[..].into()),
+                ),
+                (SigType::Ed25519, [1u8; 32].to_vec()),
+            ] {
+                let pubkey = pink::ext().get_public_key(sig_type, &privkey);
+                let account_id = AccountId::from(<[u8; 32]>::try_from(pubkey).unwrap());
+                let message = claim_message(&proof_url, &account_id);
+                let signature = pink::ext().sign(sig_type, &privkey, message.as_bytes());
+                let body = format!(
+                    "This gist is owned by address: 0x{}\nSignature: 0x{}\n",
+                    hex::encode(account_id),
+                    hex::encode(&signature)
+                );
+                let signature = extract_signature(body.as_bytes()).unwrap().unwrap();
+                assert!(verify_claim_signature(&message, &account_id, &signature));
+                // Bound to the username, the gist and the account
+                for other_url in [
+                    ProofUrl {
+                        handle: "someone".to_string(),
+                        ..proof_url.clone()
+                    },
+                    ProofUrl {
+                        nonce: "another-gist".to_string(),
+                        ..proof_url.clone()
+                    },
+                    ProofUrl {
+                        provider: Provider::GitlabSnippet,
+                        ..proof_url.clone()
+                    },
+                ] {
+                    let other = claim_message(&other_url, &account_id);
+                    assert!(!verify_claim_signature(&other, &account_id, &signature));
+                }
+                let other_account = AccountId::from([1u8; 32]);
+                assert!(!verify_claim_signature(
+                    &message,
+                    &other_account,
+                    &signature
+                ));
+                // Signed by the Polkadot.js extension
+                let wrapped = format!("<Bytes>{}</Bytes>", message);
+                let signature = pink::ext().sign(sig_type, &privkey, wrapped.as_bytes());
+                let signature = <[u8; 64]>::try_from(signature).unwrap();
+                assert!(verify_claim_signature(&message, &account_id, &signature));
+            }
+
+            // The message is built from the normalized url
+            let contract = EasyOracle::new();
+            let account_id = AccountId::from([1u8; 32]);
+            assert_eq!(
+                contract.claim_message(GIST_URL.replace("h4x3rotab", "H4x3Rotab"), account_id),
+                Ok(claim_message(&proof_url, &account_id))
+            );
+            assert_eq!(
+                contract.claim_message("https://example.com/proof".to_string(), account_id),
+                Err(Error::InvalidUrl)
+            );
+
+            // Bad cases
+            assert_eq!(extract_signature(b"This gist is owned by"), Ok(None));
+            assert_eq!(
+                extract_signature(b"Signature: 0xAB"),
+                Err(Error::InvalidClaimSignature)
+            );
+        }
+
+        #[ink::test]
+        fn config_signed_claims() {
+            pink_extension_runtime::mock_ext::mock_all_ext();
+            let accounts = default_accounts();
+            let mut contract = EasyOracle::new();
+            assert!(!contract.require_signed_claims());
+            assert!(contract.set_require_signed_claims(true).is_ok());
+            assert!(contract.require_signed_claims());
+            ink_env::test::set_caller::<Environment>(accounts.bob);
+            assert_eq!(
+                contract.set_require_signed_claims(false),
+                Err(Error::BadOrigin)
+            );
+
+            // Unsigned claims are rejected in the strict mode
+            mock_gist("h4x3rotab", b"This gist is owned by address: 0x0101010101010101010101010101010101010101010101010101010101010101");
+            let result = contract.attest(GIST_URL.to_string());
+            assert_eq!(result.err(), Some(Error::SignatureRequired.encode()));
+        }
+
+        #[ink::test]
+        fn config_api_tokens() {
             use pink_extension::chain_extension::{mock, HttpResponse};
             pink_extension_runtime::mock_ext::mock_all_ext();
+            let accounts = default_accounts();
+            let mut contract = EasyOracle::new();
+
+            // The Github API rejects the requests without the token
+            mock::mock_http_request(|request| {
+                if !request.url.starts_with("https://api.github.com/") {
+                    HttpResponse::ok(b"This gist is owned by address: 0x0101010101010101010101010101010101010101010101010101010101010101".to_vec())
+                } else if request
+                    .headers
+                    .iter()
+                    .any(|(name, value)| name == "Authorization" && value == "Bearer secret")
+                {
+                    HttpResponse::ok(br#"{"owner": {"login": "h4x3rotab"}}"#.to_vec())
+                } else {
+                    HttpResponse::not_found()
+                }
+            });
+            assert_eq!(
+                contract.attest(GIST_URL.to_string()).err(),
+                Some(Error::RequestFailed.encode())
+            );
+            assert!(contract
+                .set_api_token(Provider::GithubGist, Some("secret".to_string()))
+                .is_ok());
+            assert!(contract.attest(GIST_URL.to_string()).is_ok());
+            assert!(contract.set_api_token(Provider::GithubGist, None).is_ok());
+            assert_eq!(
+                contract.attest(GIST_URL.to_string()).err(),
+                Some(Error::RequestFailed.encode())
+            );
+
+            // Only the admin can set the tokens
+            ink_env::test::set_caller::<Environment>(accounts.bob);
+            assert_eq!(
+                contract.set_api_token(Provider::GithubGist, Some("evil".to_string())),
+                Err(Error::BadOrigin)
+            );
+        }
+
+        #[ink::test]
+        fn config_ss58_prefixes() {
+            pink_extension_runtime::mock_ext::mock_all_ext();
+            let accounts = default_accounts();
+            let mut contract = EasyOracle::new();
+            assert_eq!(contract.allowed_ss58_prefixes(), vec![0, 2, 30, 42]);
+            assert!(contract.set_allowed_ss58_prefixes(vec![30]).is_ok());
+            assert_eq!(contract.allowed_ss58_prefixes(), vec![30]);
+            ink_env::test::set_caller::<Environment>(accounts.bob);
+            assert_eq!(
+                contract.set_allowed_ss58_prefixes(vec![42]),
+                Err(Error::BadOrigin)
+            );
+        }
+
+        #[ink::test]
+        fn namespace_linked_identities() {
+            pink_extension_runtime::mock_ext::mock_all_ext();
+            let accounts = default_accounts();
+            let mut contract = EasyOracle::new();
+            // A Github username linked in the original layout
+            contract.linked_users.insert("h4x3rotab".to_string(), &());
+            let quote = |provider| IdentityQuote {
+                provider,
+                handle: "h4x3rotab".to_string(),
+                account_id: accounts.alice,
+            };
+            let github = contract
+                .attestation_generator
+                .sign(quote(Provider::GithubGist));
+            assert_eq!(contract.redeem(github), Err(Error::UsernameAlreadyInUse));
+            // The same handle on another provider is a different identity
+            let gitlab = contract
+                .attestation_generator
+                .sign(quote(Provider::GitlabSnippet));
+            assert_eq!(contract.redeem(gitlab), Err(Error::BadgeContractNotSetUp));
+        }
+
+        #[ink::test]
+        fn end_to_end() {
+            pink_extension_runtime::mock_ext::mock_all_ext();
 
             // Test accounts
             let accounts = default_accounts();
@@ -323,22 +1555,24 @@ mod easy_oracle {
                     .call_mut()
                     .add_code(id, vec!["code1".to_string(), "code2".to_string()])
                     .is_ok());
-                assert!(badges.call_mut().add_issuer(id, contract.id()).is_ok());
+                assert!(badges
+                    .call_mut()
+                    .add_issuer(id, contract.id(), None)
+                    .is_ok());
                 // Tell the oracle the badges are ready to issue
                 assert!(contract.call_mut().config_issuer(badges.id(), id).is_ok());
 
                 // Generate an attestation
                 //
                 // Mock a http request first (the 256 bits account id is the pubkey of Alice)
-                mock::mock_http_request(|_| {
-                    HttpResponse::ok(b"This gist is owned by address: 0x0101010101010101010101010101010101010101010101010101010101010101".to_vec())
-                });
-                let result = contract.call().attest("https://gist.githubusercontent.com/h4x3rotab/0cabeb528bdaf30e4cf741e26b714e04/raw/620f958fb92baba585a77c1854d68dc986803b4e/test%2520gist".to_string());
+                mock_gist("h4x3rotab", b"This gist is owned by address: 0x0101010101010101010101010101010101010101010101010101010101010101");
+                let result = contract.call().attest(GIST_URL.to_string());
                 assert!(result.is_ok());
 
                 let attestation = result.unwrap();
-                let data: GistQuote = Decode::decode(&mut &attestation.data[..]).unwrap();
-                assert_eq!(data.username, "h4x3rotab");
+                let data: IdentityQuote = Decode::decode(&mut &attestation.data[..]).unwrap();
+                assert_eq!(data.provider, Provider::GithubGist);
+                assert_eq!(data.handle, "h4x3rotab");
                 assert_eq!(data.account_id, accounts.alice);
 
                 // Before redeem
@@ -349,7 +1583,10 @@ mod easy_oracle {
                     .call_mut()
                     .redeem(attestation)
                     .expect("Should be able to issue badge");
-                assert_eq!(badges.call().get(id), Ok("code1".to_string()));
+                assert!(matches!(
+                    badges.call().get(id).as_deref(),
+                    Ok("code1" | "code2")
+                ));
             });
         }
     }
@@ -8,36 +8,153 @@ pub use crate::fat_badges::{FatBadges, Result};
 pub mod issuable {
     use ink_env::AccountId;
     use ink_lang as ink;
+    use ink_prelude::vec::Vec;
 
     #[openbrush::trait_definition(mock = crate::FatBadges)]
     pub trait Issuable {
         #[ink(message)]
         fn issue(&mut self, id: u32, dest: AccountId) -> crate::Result<()>;
+
+        #[ink(message)]
+        fn issue_batch(
+            &mut self,
+            id: u32,
+            dest: Vec<AccountId>,
+        ) -> crate::Result<Vec<crate::Result<()>>>;
     }
 
     #[openbrush::wrapper]
     pub type IssuableRef = dyn Issuable;
 }
 
+// Define a trait for other contracts to gate their features by badge ownership.
+pub mod badge_query {
+    use ink_env::AccountId;
+    use ink_lang as ink;
+
+    #[openbrush::trait_definition(mock = crate::FatBadges)]
+    pub trait BadgeQuery {
+        /// Checks if an account holds a valid (not revoked or expired) badge
+        #[ink(message)]
+        fn has_badge(&self, id: u32, account: AccountId) -> bool;
+
+        /// Returns the number of badges held by an account
+        #[ink(message)]
+        fn badge_count_of(&self, account: AccountId) -> u32;
+
+        /// Returns the timestamp when the badge was issued to an account
+        #[ink(message)]
+        fn issued_at(&self, id: u32, account: AccountId) -> Option<u64>;
+    }
+
+    #[openbrush::wrapper]
+    pub type BadgeQueryRef = dyn BadgeQuery;
+}
+
+/// The roles managed by the contract owner via `AccessControl`
+///
+/// The contract owner holds `OWNER` and all the roles in `OWNER_ROLES` implicitly. As an
+/// emergency override, the owner can also act as the admin of any badge or series. Other accounts
+/// only hold the roles granted to them. The owner can let the holders of another role manage a
+/// role by `set_role_admin()`.
+pub mod roles {
+    use openbrush::contracts::traits::access_control::RoleType;
+
+    /// The admin role of all the roles, held by the contract owner only
+    pub const OWNER: RoleType = 0;
+    /// Can create new badges
+    pub const BADGE_CREATOR: RoleType = ink_lang::selector_id!("BADGE_CREATOR");
+    /// Can upload redeem code to any badge
+    pub const CODE_UPLOADER: RoleType = ink_lang::selector_id!("CODE_UPLOADER");
+    /// Can issue any badge without an allowance
+    pub const ISSUER: RoleType = ink_lang::selector_id!("ISSUER");
+    /// Can read the redeem code stats of any badge
+    pub const AUDITOR: RoleType = ink_lang::selector_id!("AUDITOR");
+
+    /// The roles held by the contract owner without being granted
+    pub const OWNER_ROLES: [RoleType; 4] = [BADGE_CREATOR, CODE_UPLOADER, ISSUER, AUDITOR];
+}
+
 #[openbrush::contract]
 mod fat_badges {
+    use super::badge_query::*;
     use super::issuable::*;
+    use super::roles::*;
     use ink_lang::codegen::Env;
     use ink_prelude::{string::String, vec::Vec};
     use ink_storage::traits::{PackedLayout, SpreadAllocate, SpreadLayout};
     use ink_storage::Mapping;
+    use openbrush::contracts::traits::access_control::{
+        AccessControl, AccessControlError, RoleType,
+    };
+    use openbrush::contracts::traits::psp34::{
+        extensions::metadata::PSP34Metadata, Id, PSP34Error, PSP34,
+    };
     use scale::{Decode, Encode};
 
     #[ink(storage)]
     #[derive(SpreadAllocate)]
     #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
     pub struct FatBadges {
+        // The original layout (see `STORAGE_VERSION`)
         admin: AccountId,
         total_badges: u32,
         badge_info: Mapping<u32, BadgeInfo>,
         badge_issuers: Mapping<(u32, AccountId), ()>,
         badge_code: Mapping<(u32, u32), String>,
         badge_assignments: Mapping<(u32, AccountId), u32>,
+        // Appended since the original layout
+        badge_returned_code: Mapping<(u32, u32), u32>,
+        badge_revocations: Mapping<(u32, AccountId), String>,
+        badge_pending_admin: Mapping<u32, AccountId>,
+        // Secondary indexes for listing
+        badge_issuer_count: Mapping<u32, u32>,
+        badge_issuer_list: Mapping<(u32, u32), AccountId>,
+        badge_issuer_pos: Mapping<(u32, AccountId), u32>,
+        badge_holder_count: Mapping<u32, u32>,
+        badge_holder_list: Mapping<(u32, u32), AccountId>,
+        badge_holder_pos: Mapping<(u32, AccountId), u32>,
+        account_badge_count: Mapping<AccountId, u32>,
+        account_badge_list: Mapping<(AccountId, u32), u32>,
+        account_badge_pos: Mapping<(AccountId, u32), u32>,
+        // Commit-reveal redeem code
+        badge_code_hash: Mapping<(u32, u32), [u8; 32]>,
+        badge_sealed_code: Mapping<(u32, AccountId), Vec<u8>>,
+        // Issuance records
+        badge_issuance: Mapping<(u32, AccountId), Issuance>,
+        badge_code_invalid: Mapping<(u32, u32), ()>,
+        // Random code assignment
+        badge_returned_pos: Mapping<(u32, u32), u32>,
+        badge_code_perm: Mapping<(u32, u32), u32>,
+        badge_code_perm_inv: Mapping<(u32, u32), u32>,
+        roles: Mapping<(RoleType, AccountId), ()>,
+        // Emergency stop
+        badge_frozen: Mapping<u32, ()>,
+        // Export and import
+        import_open: Mapping<(), ()>,
+        // Series of badges
+        total_series: Mapping<(), u32>,
+        series_info: Mapping<u32, SeriesInfo>,
+        series_badge_count: Mapping<u32, u32>,
+        series_badge_list: Mapping<(u32, u32), u32>,
+        series_badge_pos: Mapping<(u32, u32), u32>,
+        badge_series: Mapping<u32, u32>,
+        // Composite badges
+        badge_dependent_count: Mapping<u32, u32>,
+        badge_dependent_list: Mapping<(u32, u32), u32>,
+        badge_dependent_pos: Mapping<(u32, u32), u32>,
+        pending_admin: Mapping<(), AccountId>,
+        paused: Mapping<(), ()>,
+        // Upgrade, where a missing version means the original layout
+        storage_version: Mapping<(), u32>,
+        // The remaining allowance of the issuers with a limit
+        badge_issuer_allowance: Mapping<(u32, AccountId), u32>,
+        // The composite badges approved by the admin of the prerequisite
+        badge_dependent_approvals: Mapping<(u32, u32), ()>,
+        // The committed redeem code sealed at upload time
+        badge_slot_sealed_code: Mapping<(u32, u32), Vec<u8>>,
+        // The admin role of each role, where a missing entry means `OWNER`
+        role_admins: Mapping<RoleType, RoleType>,
     }
 
     /// Errors that can occur upon calling this contract.
@@ -50,15 +167,373 @@ mod fat_badges {
         NotFound,
         RunOutOfCode,
         Duplicated,
+        Revoked,
+        WrongCodeMode,
+        CodeModeLocked,
+        NotDelivered,
+        NotStarted,
+        Ended,
+        SupplyCapReached,
+        Expired,
+        InvalidMetadata,
+        QuotaExceeded,
+        InvalidCodeSlot,
+        Paused,
+        Frozen,
+        UpgradeFailed,
+        ImportClosed,
+        InvalidSnapshot,
+        SeriesNotFound,
+        AlreadyInSeries,
+        InvalidPrerequisites,
+        InvalidIssuanceWindow,
     }
 
     /// Type alias for the contract's result type.
     pub type Result<T> = core::result::Result<T, Error>;
 
-    /// The basic information of a badge
-    #[derive(
-        Debug, PartialEq, Encode, Decode, Clone, SpreadLayout, PackedLayout, SpreadAllocate,
+    /// Emitted when a new badge is created
+    #[ink(event)]
+    pub struct BadgeCreated {
+        #[ink(topic)]
+        id: u32,
+        #[ink(topic)]
+        admin: AccountId,
+        name: String,
+    }
+
+    /// Emitted when an issuer is added to a badge
+    #[ink(event)]
+    pub struct IssuerAdded {
+        #[ink(topic)]
+        id: u32,
+        #[ink(topic)]
+        issuer: AccountId,
+        /// The number of badges the issuer can issue, or `None` for unlimited
+        allowance: Option<u32>,
+    }
+
+    /// Emitted when an issuer is removed from a badge
+    #[ink(event)]
+    pub struct IssuerRemoved {
+        #[ink(topic)]
+        id: u32,
+        #[ink(topic)]
+        issuer: AccountId,
+    }
+
+    /// Emitted when redeem code is appended to a badge
+    #[ink(event)]
+    pub struct CodeAdded {
+        #[ink(topic)]
+        id: u32,
+        /// The number of the newly added code
+        count: u32,
+    }
+
+    /// Emitted when the code mode of a badge is changed
+    #[ink(event)]
+    pub struct CodeModeChanged {
+        #[ink(topic)]
+        id: u32,
+        mode: CodeMode,
+    }
+
+    /// Emitted when the issuance rules of a badge are changed
+    #[ink(event)]
+    pub struct IssuanceRulesUpdated {
+        #[ink(topic)]
+        id: u32,
+        max_supply: Option<u32>,
+        start_time: Option<Timestamp>,
+        end_time: Option<Timestamp>,
+        validity_period: Option<Timestamp>,
+    }
+
+    /// Emitted when a badge is issued to an account
+    #[ink(event)]
+    pub struct BadgeIssued {
+        #[ink(topic)]
+        id: u32,
+        #[ink(topic)]
+        dest: AccountId,
+    }
+
+    /// Emitted when the contract admin proposes a new admin
+    #[ink(event)]
+    pub struct OwnershipTransferStarted {
+        #[ink(topic)]
+        admin: AccountId,
+        #[ink(topic)]
+        pending_admin: AccountId,
+    }
+
+    /// Emitted when the contract admin is changed
+    ///
+    /// `new_admin` is `None` when the ownership is renounced.
+    #[ink(event)]
+    pub struct OwnershipTransferred {
+        #[ink(topic)]
+        previous_admin: AccountId,
+        #[ink(topic)]
+        new_admin: Option<AccountId>,
+    }
+
+    /// Emitted when a badge admin proposes a new admin of the badge
+    #[ink(event)]
+    pub struct BadgeAdminTransferStarted {
+        #[ink(topic)]
+        id: u32,
+        #[ink(topic)]
+        pending_admin: AccountId,
+    }
+
+    /// Emitted when the admin of a badge is changed
+    ///
+    /// `new_admin` is `None` when the badge admin is renounced.
+    #[ink(event)]
+    pub struct BadgeAdminChanged {
+        #[ink(topic)]
+        id: u32,
+        #[ink(topic)]
+        previous_admin: AccountId,
+        new_admin: Option<AccountId>,
+    }
+
+    /// Emitted when a role is granted to an account
+    #[ink(event)]
+    pub struct RoleGranted {
+        #[ink(topic)]
+        role: RoleType,
+        #[ink(topic)]
+        grantee: AccountId,
+        grantor: AccountId,
+    }
+
+    /// Emitted when a role is revoked from or renounced by an account
+    #[ink(event)]
+    pub struct RoleRevoked {
+        #[ink(topic)]
+        role: RoleType,
+        #[ink(topic)]
+        account: AccountId,
+        sender: AccountId,
+    }
+
+    /// Emitted when the admin role of a role is changed
+    #[ink(event)]
+    pub struct RoleAdminChanged {
+        #[ink(topic)]
+        role: RoleType,
+        previous_admin_role: RoleType,
+        new_admin_role: RoleType,
+    }
+
+    /// Emitted when the contract is paused or unpaused
+    #[ink(event)]
+    pub struct PauseChanged {
+        #[ink(topic)]
+        admin: AccountId,
+        paused: bool,
+    }
+
+    /// Emitted when a badge is frozen or unfrozen
+    #[ink(event)]
+    pub struct BadgeFreezeChanged {
+        #[ink(topic)]
+        id: u32,
+        frozen: bool,
+    }
+
+    /// Emitted when the prerequisites of a composite badge are changed
+    #[ink(event)]
+    pub struct PrerequisitesUpdated {
+        #[ink(topic)]
+        id: u32,
+        prerequisites: Vec<u32>,
+    }
+
+    /// Emitted when the admin of a badge approves or disapproves a composite badge depending on it
+    #[ink(event)]
+    pub struct DependentApproved {
+        #[ink(topic)]
+        id: u32,
+        #[ink(topic)]
+        dependent: u32,
+        approved: bool,
+    }
+
+    /// Emitted when a new series is created
+    #[ink(event)]
+    pub struct SeriesCreated {
+        #[ink(topic)]
+        id: u32,
+        #[ink(topic)]
+        admin: AccountId,
+        name: String,
+    }
+
+    /// Emitted when a badge is added to a series
+    #[ink(event)]
+    pub struct SeriesBadgeAdded {
+        #[ink(topic)]
+        series_id: u32,
+        #[ink(topic)]
+        badge_id: u32,
+    }
+
+    /// Emitted when a badge is removed from a series
+    #[ink(event)]
+    pub struct SeriesBadgeRemoved {
+        #[ink(topic)]
+        series_id: u32,
+        #[ink(topic)]
+        badge_id: u32,
+    }
+
+    /// Emitted when the contract code is upgraded by `set_code()`
+    #[ink(event)]
+    pub struct CodeUpgraded {
+        code_hash: [u8; 32],
+    }
+
+    /// Emitted when the storage is migrated to a new version by `migrate()`
+    #[ink(event)]
+    pub struct StorageMigrated {
+        from: u32,
+        to: u32,
+    }
+
+    /// Emitted when the accounts written by the original layout are added to the lists of a badge
+    #[ink(event)]
+    pub struct AccountsMigrated {
+        #[ink(topic)]
+        id: u32,
+        /// The number of the given accounts holding the badge
+        holders: u32,
+        /// The number of the given accounts issuing the badge
+        issuers: u32,
+    }
+
+    /// Emitted when a page of the exported state is imported by `import()`
+    #[ink(event)]
+    pub struct SnapshotImported {
+        /// The number of the imported entries
+        count: u32,
+    }
+
+    /// Emitted when the import is closed
+    #[ink(event)]
+    pub struct ImportFinished {
+        #[ink(topic)]
+        admin: AccountId,
+    }
+
+    /// Emitted when a range of unissued redeem code is invalidated
+    #[ink(event)]
+    pub struct CodeInvalidated {
+        #[ink(topic)]
+        id: u32,
+        /// The first invalidated code slot
+        start: u32,
+        /// The end of the invalidated code slots (exclusive)
+        end: u32,
+    }
+
+    /// Emitted when an unissued redeem code is replaced
+    #[ink(event)]
+    pub struct CodeReplaced {
+        #[ink(topic)]
+        id: u32,
+        slot: u32,
+    }
+
+    /// Emitted when the encrypted redeem code is delivered to a holder
+    #[ink(event)]
+    pub struct CodeDelivered {
+        #[ink(topic)]
+        id: u32,
+        #[ink(topic)]
+        account: AccountId,
+    }
+
+    /// Emitted when the metadata of a badge is updated
+    #[ink(event)]
+    pub struct BadgeMetadataUpdated {
+        #[ink(topic)]
+        id: u32,
+    }
+
+    /// Emitted when a badge is revoked from an account
+    #[ink(event)]
+    pub struct BadgeRevoked {
+        #[ink(topic)]
+        id: u32,
+        #[ink(topic)]
+        account: AccountId,
+        reason: String,
+        /// Whether the redeem code is returned to the badge for reissuing
+        code_returned: bool,
+    }
+
+    /// How the redeem code of a badge is kept
+    #[derive(Debug, PartialEq, Eq, Clone, Copy, Encode, Decode, SpreadLayout, PackedLayout)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout,)
     )]
+    pub enum CodeMode {
+        /// The plaintext code is stored in the contract and revealed by `get()`
+        ///
+        /// Only safe in a confidential environment.
+        Plaintext,
+        /// Only the Blake2x256 hash of the code is stored in the contract
+        ///
+        /// The code can be sealed (encrypted off-chain) at upload time by `add_sealed_code()`,
+        /// or delivered encrypted to the holder by `deliver_code()` after the issuance. The holder
+        /// reads it by `get_sealed_code()` and can check it against the hash by `verify_code()`.
+        Committed,
+    }
+
+    /// The maximum number of attributes in `BadgeMetadata`
+    const MAX_ATTRIBUTES: usize = 16;
+
+    /// The max number of the prerequisites of a composite badge
+    const MAX_PREREQUISITES: usize = 8;
+
+    /// The max number of the composite badges depending on a badge, bounding the work of `issue()`
+    const MAX_DEPENDENTS: u32 = 16;
+
+    /// The version of the storage layout of this code
+    ///
+    /// The original layout is version 0. Bump it when a migration is needed in `migrate()`. The
+    /// root storage struct is loaded eagerly field by field, so the original fields must stay
+    /// first in their order, and the new fields must be appended as `Mapping`s (even a single
+    /// value, keyed by `()`) to keep the old storage loadable.
+    pub const STORAGE_VERSION: u32 = 1;
+
+    /// The display information of a badge for the front-ends
+    #[derive(Debug, Default, PartialEq, Eq, Encode, Decode, Clone, SpreadLayout, PackedLayout)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout,)
+    )]
+    pub struct BadgeMetadata {
+        /// Description of the badge
+        description: String,
+        /// URI of the badge image
+        image_uri: String,
+        /// Link to an external page about the badge
+        external_link: String,
+        /// Extra key-value attributes (at most `MAX_ATTRIBUTES`)
+        attributes: Vec<(String, String)>,
+    }
+
+    /// The basic information of a badge
+    ///
+    /// The fields after `num_issued` are appended to the original layout. See the `Decode`
+    /// implementation below.
+    #[derive(Debug, PartialEq, Encode, Clone, SpreadLayout, PackedLayout)]
     #[cfg_attr(
         feature = "std",
         derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout,)
@@ -74,6 +549,206 @@ mod fat_badges {
         num_code: u32,
         /// The number of issued badges
         num_issued: u32,
+        /// The number of code slots drawn from the uploaded code (see `take_code()`)
+        next_code: u32,
+        /// The number of revoked redeem code waiting to be reissued
+        num_returned: u32,
+        /// How the redeem code is kept
+        code_mode: CodeMode,
+        /// The maximum number of accounts holding the badge at the same time
+        max_supply: Option<u32>,
+        /// The badge can't be issued before this time (in milliseconds)
+        start_time: Option<Timestamp>,
+        /// The badge can't be issued since this time (in milliseconds)
+        end_time: Option<Timestamp>,
+        /// How long an issued badge is valid (in milliseconds)
+        ///
+        /// Counted from the issuance time of each holder when checked, so a change applies to
+        /// the issued badges as well.
+        validity_period: Option<Timestamp>,
+        /// The display information
+        metadata: BadgeMetadata,
+        /// The number of invalidated redeem code not drawn by `take_code()` yet
+        num_invalid: u32,
+        /// The badges to hold before this badge is issued automatically
+        ///
+        /// A badge with prerequisites is a composite badge.
+        prerequisites: Vec<u32>,
+    }
+
+    /// Decodes a `BadgeInfo`, accepting the entries written in an older layout
+    ///
+    /// The storage entries written before a field was appended end early. The missing fields are
+    /// filled with the values matching the old behavior.
+    impl Decode for BadgeInfo {
+        fn decode<I: scale::Input>(input: &mut I) -> core::result::Result<Self, scale::Error> {
+            let id = Decode::decode(input)?;
+            let admin = Decode::decode(input)?;
+            let name = Decode::decode(input)?;
+            let num_code = Decode::decode(input)?;
+            let num_issued = Decode::decode(input)?;
+            Ok(BadgeInfo {
+                id,
+                admin,
+                name,
+                num_code,
+                num_issued,
+                // The code was issued sequentially
+                next_code: decode_appended(input, num_issued)?,
+                num_returned: decode_appended(input, 0)?,
+                code_mode: decode_appended(input, CodeMode::Plaintext)?,
+                max_supply: decode_appended(input, None)?,
+                start_time: decode_appended(input, None)?,
+                end_time: decode_appended(input, None)?,
+                validity_period: decode_appended(input, None)?,
+                metadata: decode_appended(input, BadgeMetadata::default())?,
+                num_invalid: decode_appended(input, 0)?,
+                prerequisites: decode_appended(input, Vec::new())?,
+            })
+        }
+    }
+
+    /// The record of a badge issuance
+    #[derive(Debug, PartialEq, Eq, Encode, Decode, Clone, SpreadLayout, PackedLayout)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout,)
+    )]
+    pub struct Issuance {
+        /// The block timestamp of the issuance (in milliseconds)
+        timestamp: Timestamp,
+        /// The block number of the issuance
+        block_number: BlockNumber,
+        /// The account calling `issue()`, or the contract itself for the composite badges
+        issuer: AccountId,
+    }
+
+    /// Decodes a field appended to a storage layout, or returns `default` if the input has ended
+    fn decode_appended<T: Decode, I: scale::Input>(
+        input: &mut I,
+        default: T,
+    ) -> core::result::Result<T, scale::Error> {
+        if input.remaining_len()? == Some(0) {
+            Ok(default)
+        } else {
+            T::decode(input)
+        }
+    }
+
+    impl BadgeInfo {
+        /// Returns the number of redeem code that can be issued
+        fn num_available(&self) -> u32 {
+            self.num_code - self.next_code + self.num_returned - self.num_invalid
+        }
+    }
+
+    /// A family of badges, e.g. the editions of a workshop
+    #[derive(Debug, PartialEq, Eq, Encode, Decode, Clone, SpreadLayout, PackedLayout)]
+    #[cfg_attr(
+        feature = "std",
+        derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout,)
+    )]
+    pub struct SeriesInfo {
+        /// The id of the series
+        id: u32,
+        /// The admin to manage the series
+        admin: AccountId,
+        /// Name of the series
+        name: String,
+    }
+
+    /// The badges of a series held by an account
+    #[derive(Debug, PartialEq, Eq, Encode, Decode, Clone)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct SeriesProgress {
+        /// The number of the valid badges of the series held by the account
+        held: u32,
+        /// The number of the badges in the series
+        total: u32,
+    }
+
+    impl SeriesProgress {
+        /// Checks if the account holds all the badges of a non-empty series
+        pub fn completed(&self) -> bool {
+            self.total > 0 && self.held == self.total
+        }
+    }
+
+    /// An entry of the exported state, replayed in order by `import()`
+    #[derive(Debug, PartialEq, Encode, Decode, Clone)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum SnapshotEntry {
+        /// A badge, which must be imported with the next badge id
+        Badge(BadgeInfo),
+        /// An issuer of a badge with its remaining allowance
+        Issuer {
+            id: u32,
+            issuer: AccountId,
+            allowance: Option<u32>,
+        },
+        /// A plaintext redeem code slot
+        Code {
+            id: u32,
+            slot: u32,
+            code: String,
+            state: CodeSlotState,
+        },
+        /// A committed redeem code slot
+        CodeHash {
+            id: u32,
+            slot: u32,
+            hash: [u8; 32],
+            state: CodeSlotState,
+        },
+        /// A badge held by an account
+        Assignment {
+            id: u32,
+            holder: AccountId,
+            slot: u32,
+            /// `None` for the badges issued by the original layout
+            issuance: Option<Issuance>,
+            sealed_code: Option<Vec<u8>>,
+        },
+        /// A frozen badge
+        Frozen { id: u32 },
+        /// A series, which must be imported with the next series id
+        Series(SeriesInfo),
+        /// A badge in a series
+        SeriesBadge { series_id: u32, badge_id: u32 },
+        /// The committed redeem code of a slot sealed at upload time
+        SealedCode {
+            id: u32,
+            slot: u32,
+            sealed_code: Vec<u8>,
+        },
+    }
+
+    /// The issuance state of a redeem code slot (see `take_code()`)
+    #[derive(Debug, PartialEq, Eq, Encode, Decode, Clone)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct CodeSlotState {
+        /// The position of the slot in the shuffled code
+        pos: u32,
+        /// The position in the returned code if the slot was returned
+        returned_pos: Option<u32>,
+        /// Whether the slot is invalidated
+        invalid: bool,
+    }
+
+    /// The redeem code stats of a badge
+    #[derive(Debug, PartialEq, Eq, Encode, Decode, Clone)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub struct CodeInventory {
+        /// The number of all the code slots
+        total: u32,
+        /// The number of the code held by accounts
+        issued: u32,
+        /// The number of the code that can be issued
+        available: u32,
+        /// The number of the revoked code waiting to be reissued
+        returned: u32,
+        /// The number of the invalidated code which is never issued
+        invalidated: u32,
     }
 
     impl FatBadges {
@@ -82,6 +757,8 @@ mod fat_badges {
             ink_lang::utils::initialize_contract(|this: &mut Self| {
                 this.admin = Self::env().caller();
                 this.total_badges = 0;
+                this.storage_version.insert((), &STORAGE_VERSION);
+                this.import_open.insert((), &());
             })
         }
 
@@ -89,49 +766,97 @@ mod fat_badges {
 
         /// Creates a new badge and become the admin of the badge
         ///
-        /// Return the id of the badge.
+        /// The caller must have the `BADGE_CREATOR` role. Return the id of the badge.
         #[ink(message)]
         pub fn new_badge(&mut self, name: String) -> Result<u32> {
             let caller = self.env().caller();
+            if !self.has_role(BADGE_CREATOR, caller) {
+                return Err(Error::BadOrigin);
+            }
+            self.close_import()?;
             let id = self.total_badges;
             let badge = BadgeInfo {
                 id,
                 admin: caller,
-                name,
+                name: name.clone(),
                 num_code: 0,
                 num_issued: 0,
+                next_code: 0,
+                num_returned: 0,
+                code_mode: CodeMode::Plaintext,
+                max_supply: None,
+                start_time: None,
+                end_time: None,
+                validity_period: None,
+                metadata: BadgeMetadata::default(),
+                num_invalid: 0,
+                prerequisites: Vec::new(),
             };
             self.badge_info.insert(id, &badge);
             self.total_badges += 1;
+            self.env().emit_event(BadgeCreated {
+                id,
+                admin: caller,
+                name,
+            });
             Ok(id)
         }
 
         /// Adds a badge issuer
         ///
-        /// The caller must be the badge admin.
+        /// The caller must be the badge admin. The issuer can issue at most `allowance` badges,
+        /// or unlimited badges if it's `None`. Adding an existing issuer resets its allowance.
+        /// Not allowed when the contract is paused or the badge is frozen.
         #[ink(message)]
-        pub fn add_issuer(&mut self, id: u32, issuer: AccountId) -> Result<()> {
+        pub fn add_issuer(
+            &mut self,
+            id: u32,
+            issuer: AccountId,
+            allowance: Option<u32>,
+        ) -> Result<()> {
             self.ensure_badge_admin(id)?;
-            self.badge_issuers.insert((id, issuer), &());
+            self.ensure_active(id)?;
+            self.insert_issuer(id, issuer, allowance);
+            self.env().emit_event(IssuerAdded {
+                id,
+                issuer,
+                allowance,
+            });
             Ok(())
         }
 
         /// Removes a badge issuer
         ///
-        /// The caller must be the badge admin.
+        /// The caller must be the badge admin. Not allowed when the contract is paused or the
+        /// badge is frozen.
         #[ink(message)]
         pub fn remove_issuer(&mut self, id: u32, issuer: AccountId) -> Result<()> {
             self.ensure_badge_admin(id)?;
+            self.ensure_active(id)?;
             self.badge_issuers.remove((id, issuer));
+            self.badge_issuer_allowance.remove((id, issuer));
+            index_remove(
+                &mut self.badge_issuer_count,
+                &mut self.badge_issuer_list,
+                &mut self.badge_issuer_pos,
+                id,
+                issuer,
+            );
+            self.env().emit_event(IssuerRemoved { id, issuer });
             Ok(())
         }
 
         /// Appends a list of redeem code to a badge
         ///
-        /// The caller must be the badge admin.
+        /// The caller must be the badge admin or have the `CODE_UPLOADER` role. Not allowed when
+        /// the contract is paused or the badge is frozen.
         #[ink(message)]
         pub fn add_code(&mut self, id: u32, code: Vec<String>) -> Result<()> {
-            let mut badge = self.ensure_badge_admin(id)?;
+            let mut badge = self.ensure_badge_role(id, CODE_UPLOADER)?;
+            self.ensure_active(id)?;
+            if badge.code_mode != CodeMode::Plaintext {
+                return Err(Error::WrongCodeMode);
+            }
             let start = badge.num_code;
             badge.num_code += code.len() as u32;
             for (i, entry) in code.iter().enumerate() {
@@ -139,204 +864,4607 @@ mod fat_badges {
                 self.badge_code.insert((id, idx), entry);
             }
             self.badge_info.insert(id, &badge);
+            self.env().emit_event(CodeAdded {
+                id,
+                count: code.len() as u32,
+            });
             Ok(())
         }
 
-        // Queries
-
-        /// Returns the number of all the badges
+        /// Sets how the redeem code of a badge is kept
+        ///
+        /// The caller must be the badge admin. The mode can only be changed before any code is
+        /// added.
         #[ink(message)]
-        pub fn get_total_badges(&self) -> u32 {
-            self.total_badges
+        pub fn set_code_mode(&mut self, id: u32, mode: CodeMode) -> Result<()> {
+            let mut badge = self.ensure_badge_admin(id)?;
+            if badge.num_code > 0 {
+                return Err(Error::CodeModeLocked);
+            }
+            badge.code_mode = mode;
+            self.badge_info.insert(id, &badge);
+            self.env().emit_event(CodeModeChanged { id, mode });
+            Ok(())
         }
 
-        /// Returns the detailed information of a badge
+        /// Appends a list of redeem code hashes to a badge
+        ///
+        /// The caller must be the badge admin or have the `CODE_UPLOADER` role, and the badge
+        /// must be in `Committed` mode. Each hash is the Blake2x256 hash of a redeem code, taking
+        /// a code slot like `add_code()`. Not allowed when the contract is paused or the badge is
+        /// frozen.
         #[ink(message)]
-        pub fn get_badge_info(&self, id: u32) -> Result<BadgeInfo> {
-            self.badge_info.get(id).ok_or(Error::BadgeNotFound)
+        pub fn add_code_hashes(&mut self, id: u32, hashes: Vec<[u8; 32]>) -> Result<()> {
+            let code = hashes.into_iter().map(|hash| (hash, None)).collect();
+            self.append_committed_code(id, code)
         }
 
-        /// Checks if an account is a badge issuer
+        /// Appends a list of sealed redeem code to a badge
+        ///
+        /// The same as `add_code_hashes()`, but each entry also carries the code sealed
+        /// (encrypted off-chain) by the uploader. The holder of the slot reads it by
+        /// `get_sealed_code()` right after the issuance, so the badge admin doesn't have to
+        /// `deliver_code()` to each holder.
         #[ink(message)]
-        pub fn is_badge_issuer(&self, id: u32, issuer: AccountId) -> bool {
-            self.badge_issuers.contains((id, issuer))
+        pub fn add_sealed_code(&mut self, id: u32, code: Vec<([u8; 32], Vec<u8>)>) -> Result<()> {
+            let code = code
+                .into_iter()
+                .map(|(hash, sealed_code)| (hash, Some(sealed_code)))
+                .collect();
+            self.append_committed_code(id, code)
         }
 
-        /// Reads the badge code assigned to the caller if exists
+        /// Invalidates the unissued code in the slots `[start, end)`
+        ///
+        /// The caller must be the badge admin. The invalidated code will be skipped by `issue()`.
+        /// Returns an `InvalidCodeSlot` error if any slot in the range has been issued or doesn't
+        /// exist. An invalidated slot can be recovered by `replace_code()` or
+        /// `replace_code_hash()`.
         #[ink(message)]
-        pub fn get(&self, id: u32) -> Result<String> {
-            let caller = self.env().caller();
-            let code_idx = self
-                .badge_assignments
-                .get((id, caller))
-                .ok_or(Error::NotFound)?;
-            let code = self
-                .badge_code
-                .get((id, code_idx))
-                .expect("Assigned code exists; qed.");
-            Ok(code)
+        pub fn invalidate_code(&mut self, id: u32, start: u32, end: u32) -> Result<()> {
+            let mut badge = self.ensure_badge_admin(id)?;
+            if start > end || !(start..end).all(|slot| self.is_unissued(&badge, slot)) {
+                return Err(Error::InvalidCodeSlot);
+            }
+            for slot in start..end {
+                if !self.badge_code_invalid.contains((id, slot)) {
+                    self.badge_code_invalid.insert((id, slot), &());
+                    badge.num_invalid += 1;
+                }
+            }
+            self.badge_info.insert(id, &badge);
+            self.env().emit_event(CodeInvalidated { id, start, end });
+            Ok(())
         }
 
-        // Helper functions
+        /// Replaces the unissued plaintext code in a slot
+        ///
+        /// The caller must be the badge admin, and the badge must be in `Plaintext` mode. An
+        /// invalidated slot becomes valid again unless it was skipped by `issue()` already.
+        #[ink(message)]
+        pub fn replace_code(&mut self, id: u32, slot: u32, code: String) -> Result<()> {
+            let badge = self.ensure_replaceable_code(id, slot, CodeMode::Plaintext)?;
+            self.badge_code.insert((id, slot), &code);
+            self.revalidate_code(badge, slot);
+            Ok(())
+        }
 
-        /// Returns the badge info if it exists
-        fn ensure_badge(&self, id: u32) -> Result<BadgeInfo> {
-            self.badge_info.get(id).ok_or(Error::BadgeNotFound)
+        /// Replaces the unissued code hash in a slot
+        ///
+        /// The same as `replace_code()`, but for the badges in `Committed` mode. `hash` is the
+        /// Blake2x256 hash of the new code, so the code itself never appears on chain. The code
+        /// sealed at upload time is dropped, so the new code must be delivered by
+        /// `deliver_code()`.
+        #[ink(message)]
+        pub fn replace_code_hash(&mut self, id: u32, slot: u32, hash: [u8; 32]) -> Result<()> {
+            let badge = self.ensure_replaceable_code(id, slot, CodeMode::Committed)?;
+            self.badge_code_hash.insert((id, slot), &hash);
+            self.badge_slot_sealed_code.remove((id, slot));
+            self.revalidate_code(badge, slot);
+            Ok(())
         }
 
-        /// Returns the badge if the it exists and the caller is the admin
-        fn ensure_badge_admin(&self, id: u32) -> Result<BadgeInfo> {
-            let caller = self.env().caller();
-            let badge = self.badge_info.get(id).ok_or(Error::BadgeNotFound)?;
-            if badge.admin != caller {
-                return Err(Error::BadOrigin);
+        /// Delivers the redeem code to a holder of a `Committed` badge
+        ///
+        /// The caller must be the badge admin. `sealed_code` is the code of the slot assigned to
+        /// the holder (see `get_code_slot()`), encrypted to the holder's public key off-chain. It
+        /// takes precedence over the code sealed at upload time.
+        #[ink(message)]
+        pub fn deliver_code(
+            &mut self,
+            id: u32,
+            account: AccountId,
+            sealed_code: Vec<u8>,
+        ) -> Result<()> {
+            let badge = self.ensure_badge_admin(id)?;
+            if badge.code_mode != CodeMode::Committed {
+                return Err(Error::WrongCodeMode);
             }
-            Ok(badge)
+            if !self.badge_assignments.contains((id, account)) {
+                return Err(Error::NotFound);
+            }
+            self.badge_sealed_code.insert((id, account), &sealed_code);
+            self.env().emit_event(CodeDelivered { id, account });
+            Ok(())
         }
-    }
 
-    impl Issuable for FatBadges {
-        /// Issues a badge to the `dest` account
+        /// Updates the display information of a badge
         ///
-        /// The caller must be the badge admin or a badge issuer. Return a `RunOutOfCode` error
-        /// when there's no enough redeem code to issue.
+        /// The caller must be the badge admin. Returns an `InvalidMetadata` error if there are
+        /// more than `MAX_ATTRIBUTES` attributes.
         #[ink(message)]
-        fn issue(&mut self, id: u32, dest: AccountId) -> Result<()> {
-            let caller = self.env().caller();
-            let mut badge = self.ensure_badge(id)?;
-            // Allow the badge issuers or the badge admin
-            if !self.badge_issuers.contains((id, caller)) && caller != badge.admin {
-                return Err(Error::NotAnIssuer);
-            }
-            // Make sure we don't issue more than what we have
-            if badge.num_issued >= badge.num_code {
-                return Err(Error::RunOutOfCode);
+        pub fn update_badge_metadata(&mut self, id: u32, metadata: BadgeMetadata) -> Result<()> {
+            let mut badge = self.ensure_badge_admin(id)?;
+            if metadata.attributes.len() > MAX_ATTRIBUTES {
+                return Err(Error::InvalidMetadata);
             }
-            // No duplication
-            if self.badge_assignments.contains((id, dest)) {
+            badge.metadata = metadata;
+            self.badge_info.insert(id, &badge);
+            self.env().emit_event(BadgeMetadataUpdated { id });
+            Ok(())
+        }
+
+        /// Sets the issuance rules of a badge
+        ///
+        /// The caller must be the badge admin. `None` removes the limit. `start_time` and
+        /// `end_time` are the block timestamps (in milliseconds) limiting `issue()`. Returns an
+        /// `InvalidIssuanceWindow` error if `start_time` is not before `end_time`.
+        ///
+        /// `validity_period` is how long an issued badge stays valid. It's not fixed at the
+        /// issuance: changing it shortens or extends the validity of the issued badges as well,
+        /// e.g. to revive the expired badges.
+        #[ink(message)]
+        pub fn set_issuance_rules(
+            &mut self,
+            id: u32,
+            max_supply: Option<u32>,
+            start_time: Option<Timestamp>,
+            end_time: Option<Timestamp>,
+            validity_period: Option<Timestamp>,
+        ) -> Result<()> {
+            let mut badge = self.ensure_badge_admin(id)?;
+            if matches!((start_time, end_time), (Some(start), Some(end)) if start >= end) {
+                return Err(Error::InvalidIssuanceWindow);
+            }
+            badge.max_supply = max_supply;
+            badge.start_time = start_time;
+            badge.end_time = end_time;
+            badge.validity_period = validity_period;
+            self.badge_info.insert(id, &badge);
+            self.env().emit_event(IssuanceRulesUpdated {
+                id,
+                max_supply,
+                start_time,
+                end_time,
+                validity_period,
+            });
+            Ok(())
+        }
+
+        /// Makes a badge a composite badge of the prerequisite badges
+        ///
+        /// The caller must be the badge admin. Once an account holds all the prerequisites, the
+        /// badge is issued to it automatically by `issue()` as long as the badge is issuable.
+        /// An empty list turns it back to a normal badge. Returns an `InvalidPrerequisites`
+        /// error if there are more than `MAX_PREREQUISITES` prerequisites, any of them doesn't
+        /// exist or is duplicated, they form a cycle, or a new prerequisite already has
+        /// `MAX_DEPENDENTS` dependents.
+        ///
+        /// Each new prerequisite must be managed by the caller, or approve the badge by
+        /// `approve_dependent()`. Otherwise returns a `BadOrigin` error.
+        #[ink(message)]
+        pub fn set_prerequisites(&mut self, id: u32, prerequisites: Vec<u32>) -> Result<()> {
+            let mut badge = self.ensure_badge_admin(id)?;
+            if prerequisites.len() > MAX_PREREQUISITES
+                || prerequisites
+                    .iter()
+                    .enumerate()
+                    .any(|(i, p)| *p >= self.total_badges || prerequisites[..i].contains(p))
+                || self.depends_on(&prerequisites, id)
+            {
+                return Err(Error::InvalidPrerequisites);
+            }
+            let caller = self.env().caller();
+            for prerequisite in prerequisites
+                .iter()
+                .filter(|p| !badge.prerequisites.contains(p))
+            {
+                if self.badge_dependent_count.get(prerequisite).unwrap_or(0) >= MAX_DEPENDENTS {
+                    return Err(Error::InvalidPrerequisites);
+                }
+                let admin = self.ensure_badge(*prerequisite)?.admin;
+                if caller != admin
+                    && caller != self.admin
+                    && !self.badge_dependent_approvals.contains((*prerequisite, id))
+                {
+                    return Err(Error::BadOrigin);
+                }
+            }
+            for prerequisite in badge.prerequisites.iter() {
+                index_remove(
+                    &mut self.badge_dependent_count,
+                    &mut self.badge_dependent_list,
+                    &mut self.badge_dependent_pos,
+                    *prerequisite,
+                    id,
+                );
+            }
+            for prerequisite in prerequisites.iter() {
+                index_insert(
+                    &mut self.badge_dependent_count,
+                    &mut self.badge_dependent_list,
+                    &mut self.badge_dependent_pos,
+                    *prerequisite,
+                    id,
+                );
+            }
+            badge.prerequisites = prerequisites.clone();
+            self.badge_info.insert(id, &badge);
+            self.env()
+                .emit_event(PrerequisitesUpdated { id, prerequisites });
+            Ok(())
+        }
+
+        /// Approves or disapproves a badge to require badge `id` as a prerequisite
+        ///
+        /// The caller must be the admin of badge `id`. The approval is checked when `dependent`
+        /// adds badge `id` by `set_prerequisites()`. Disapproving doesn't remove an added
+        /// prerequisite.
+        #[ink(message)]
+        pub fn approve_dependent(&mut self, id: u32, dependent: u32, approved: bool) -> Result<()> {
+            self.ensure_badge_admin(id)?;
+            self.ensure_badge(dependent)?;
+            if approved {
+                self.badge_dependent_approvals.insert((id, dependent), &());
+            } else {
+                self.badge_dependent_approvals.remove((id, dependent));
+            }
+            self.env().emit_event(DependentApproved {
+                id,
+                dependent,
+                approved,
+            });
+            Ok(())
+        }
+
+        /// Revokes a badge from an account
+        ///
+        /// The caller must be the badge admin. The `reason` is recorded and can be read by
+        /// `get_revocation()`. When `return_code` is true, the redeem code is returned to the badge
+        /// and will be issued again. Only return the code when it's not revealed by the holder.
+        #[ink(message)]
+        pub fn revoke(
+            &mut self,
+            id: u32,
+            account: AccountId,
+            reason: String,
+            return_code: bool,
+        ) -> Result<()> {
+            let mut badge = self.ensure_badge_admin(id)?;
+            let idx = self
+                .badge_assignments
+                .get((id, account))
+                .ok_or(Error::NotFound)?;
+            self.badge_assignments.remove((id, account));
+            self.remove_holder(id, account);
+            self.badge_issuance.remove((id, account));
+            self.badge_sealed_code.remove((id, account));
+            if return_code {
+                self.badge_returned_code
+                    .insert((id, badge.num_returned), &idx);
+                self.badge_returned_pos
+                    .insert((id, idx), &badge.num_returned);
+                badge.num_returned += 1;
+            }
+            badge.num_issued -= 1;
+            self.badge_info.insert(id, &badge);
+            self.badge_revocations.insert((id, account), &reason);
+            self.env().emit_event(BadgeRevoked {
+                id,
+                account,
+                reason,
+                code_returned: return_code,
+            });
+            Ok(())
+        }
+
+        /// Proposes a new contract admin
+        ///
+        /// The caller must be the contract admin. The new admin takes over the contract after
+        /// calling `accept_ownership()`.
+        #[ink(message)]
+        pub fn transfer_ownership(&mut self, new_admin: AccountId) -> Result<()> {
+            self.ensure_admin()?;
+            self.pending_admin.insert((), &new_admin);
+            self.env().emit_event(OwnershipTransferStarted {
+                admin: self.admin,
+                pending_admin: new_admin,
+            });
+            Ok(())
+        }
+
+        /// Accepts the contract ownership proposed by `transfer_ownership()`
+        ///
+        /// The caller must be the pending contract admin.
+        #[ink(message)]
+        pub fn accept_ownership(&mut self) -> Result<()> {
+            let caller = self.env().caller();
+            if self.pending_admin.get(()) != Some(caller) {
+                return Err(Error::BadOrigin);
+            }
+            let previous_admin = self.admin;
+            self.admin = caller;
+            self.pending_admin.remove(());
+            self.env().emit_event(OwnershipTransferred {
+                previous_admin,
+                new_admin: Some(caller),
+            });
+            Ok(())
+        }
+
+        /// Gives up the contract ownership, leaving the contract without an admin
+        ///
+        /// The caller must be the contract admin.
+        #[ink(message)]
+        pub fn renounce_ownership(&mut self) -> Result<()> {
+            self.ensure_admin()?;
+            let previous_admin = self.admin;
+            self.admin = zero_account();
+            self.pending_admin.remove(());
+            self.env().emit_event(OwnershipTransferred {
+                previous_admin,
+                new_admin: None,
+            });
+            Ok(())
+        }
+
+        /// Proposes a new admin of a badge
+        ///
+        /// The caller must be the badge admin. The new admin takes over the badge after calling
+        /// `accept_badge_admin()`.
+        #[ink(message)]
+        pub fn transfer_badge_admin(&mut self, id: u32, new_admin: AccountId) -> Result<()> {
+            self.ensure_badge_admin(id)?;
+            self.badge_pending_admin.insert(id, &new_admin);
+            self.env().emit_event(BadgeAdminTransferStarted {
+                id,
+                pending_admin: new_admin,
+            });
+            Ok(())
+        }
+
+        /// Accepts the badge admin role proposed by `transfer_badge_admin()`
+        ///
+        /// The caller must be the pending badge admin.
+        #[ink(message)]
+        pub fn accept_badge_admin(&mut self, id: u32) -> Result<()> {
+            let caller = self.env().caller();
+            let mut badge = self.ensure_badge(id)?;
+            if self.badge_pending_admin.get(id) != Some(caller) {
+                return Err(Error::BadOrigin);
+            }
+            let previous_admin = badge.admin;
+            badge.admin = caller;
+            self.badge_info.insert(id, &badge);
+            self.badge_pending_admin.remove(id);
+            self.env().emit_event(BadgeAdminChanged {
+                id,
+                previous_admin,
+                new_admin: Some(caller),
+            });
+            Ok(())
+        }
+
+        /// Gives up the badge admin role, leaving the badge without an admin
+        ///
+        /// The caller must be the badge admin. The existing issuers can still issue the badge.
+        #[ink(message)]
+        pub fn renounce_badge_admin(&mut self, id: u32) -> Result<()> {
+            let mut badge = self.ensure_badge_admin(id)?;
+            let previous_admin = badge.admin;
+            badge.admin = zero_account();
+            self.badge_info.insert(id, &badge);
+            self.badge_pending_admin.remove(id);
+            self.env().emit_event(BadgeAdminChanged {
+                id,
+                previous_admin,
+                new_admin: None,
+            });
+            Ok(())
+        }
+
+        /// Sets the role whose holders can grant and revoke `role`
+        ///
+        /// The caller must be the contract owner. The admin role of the `OWNER` role can't be
+        /// changed.
+        #[ink(message)]
+        pub fn set_role_admin(&mut self, role: RoleType, admin_role: RoleType) -> Result<()> {
+            self.ensure_admin()?;
+            if role == OWNER {
+                return Err(Error::BadOrigin);
+            }
+            let previous_admin_role = self.get_role_admin(role);
+            if admin_role == OWNER {
+                self.role_admins.remove(role);
+            } else {
+                self.role_admins.insert(role, &admin_role);
+            }
+            self.env().emit_event(RoleAdminChanged {
+                role,
+                previous_admin_role,
+                new_admin_role: admin_role,
+            });
+            Ok(())
+        }
+
+        /// Pauses the contract in an emergency
+        ///
+        /// The caller must be the contract admin. Issuing badges, adding code and changing
+        /// issuers are blocked until `unpause()` is called.
+        #[ink(message)]
+        pub fn pause(&mut self) -> Result<()> {
+            self.set_paused(true)
+        }
+
+        /// Unpauses the contract
+        ///
+        /// The caller must be the contract admin.
+        #[ink(message)]
+        pub fn unpause(&mut self) -> Result<()> {
+            self.set_paused(false)
+        }
+
+        /// Freezes a badge in an emergency
+        ///
+        /// The caller must be the badge admin. Issuing the badge, adding code and changing the
+        /// issuers are blocked until `unfreeze_badge()` is called.
+        #[ink(message)]
+        pub fn freeze_badge(&mut self, id: u32) -> Result<()> {
+            self.ensure_badge_admin(id)?;
+            self.badge_frozen.insert(id, &());
+            self.env()
+                .emit_event(BadgeFreezeChanged { id, frozen: true });
+            Ok(())
+        }
+
+        /// Unfreezes a badge
+        ///
+        /// The caller must be the badge admin.
+        #[ink(message)]
+        pub fn unfreeze_badge(&mut self, id: u32) -> Result<()> {
+            self.ensure_badge_admin(id)?;
+            self.badge_frozen.remove(id);
+            self.env()
+                .emit_event(BadgeFreezeChanged { id, frozen: false });
+            Ok(())
+        }
+
+        /// Creates a new series and become the admin of the series
+        ///
+        /// The caller must have the `BADGE_CREATOR` role. Return the id of the series.
+        #[ink(message)]
+        pub fn new_series(&mut self, name: String) -> Result<u32> {
+            let caller = self.env().caller();
+            if !self.has_role(BADGE_CREATOR, caller) {
+                return Err(Error::BadOrigin);
+            }
+            self.close_import()?;
+            let id = self.total_series.get(()).unwrap_or(0);
+            let series = SeriesInfo {
+                id,
+                admin: caller,
+                name: name.clone(),
+            };
+            self.series_info.insert(id, &series);
+            self.total_series.insert((), &(id + 1));
+            self.env().emit_event(SeriesCreated {
+                id,
+                admin: caller,
+                name,
+            });
+            Ok(id)
+        }
+
+        /// Adds a badge to a series
+        ///
+        /// The caller must be the admin of both the series and the badge. A badge can be in at
+        /// most one series.
+        #[ink(message)]
+        pub fn add_to_series(&mut self, series_id: u32, badge_id: u32) -> Result<()> {
+            self.ensure_series_admin(series_id)?;
+            self.ensure_badge_admin(badge_id)?;
+            if self.badge_series.contains(badge_id) {
+                return Err(Error::AlreadyInSeries);
+            }
+            self.badge_series.insert(badge_id, &series_id);
+            index_insert(
+                &mut self.series_badge_count,
+                &mut self.series_badge_list,
+                &mut self.series_badge_pos,
+                series_id,
+                badge_id,
+            );
+            self.env().emit_event(SeriesBadgeAdded {
+                series_id,
+                badge_id,
+            });
+            Ok(())
+        }
+
+        /// Removes a badge from a series
+        ///
+        /// The caller must be the series admin.
+        #[ink(message)]
+        pub fn remove_from_series(&mut self, series_id: u32, badge_id: u32) -> Result<()> {
+            self.ensure_series_admin(series_id)?;
+            if self.badge_series.get(badge_id) != Some(series_id) {
+                return Err(Error::NotFound);
+            }
+            self.badge_series.remove(badge_id);
+            index_remove(
+                &mut self.series_badge_count,
+                &mut self.series_badge_list,
+                &mut self.series_badge_pos,
+                series_id,
+                badge_id,
+            );
+            self.env().emit_event(SeriesBadgeRemoved {
+                series_id,
+                badge_id,
+            });
+            Ok(())
+        }
+
+        /// Upgrades the contract code
+        ///
+        /// The caller must be the contract admin. The storage is kept. Call `migrate()` after the
+        /// upgrade if the new code has a newer `STORAGE_VERSION`.
+        #[ink(message)]
+        pub fn set_code(&mut self, code_hash: [u8; 32]) -> Result<()> {
+            self.ensure_admin()?;
+            ink_env::set_code_hash(&code_hash).or(Err(Error::UpgradeFailed))?;
+            self.env().emit_event(CodeUpgraded { code_hash });
+            Ok(())
+        }
+
+        /// Migrates the badges `[start, start + limit)` to the current storage layout
+        ///
+        /// The caller must be the contract admin. Each `BadgeInfo` is decoded from the old layout
+        /// and written back in the current layout. Returns the id of the next badge to migrate,
+        /// or `None` when all the badges are migrated and the storage version is updated.
+        ///
+        /// The holders and issuers written by the original layout are not in the holder and
+        /// issuer lists. Add them by `migrate_accounts()`.
+        #[ink(message)]
+        pub fn migrate(&mut self, start: u32, limit: u32) -> Result<Option<u32>> {
+            self.ensure_admin()?;
+            let end = start.saturating_add(limit).min(self.total_badges);
+            for id in start..end {
+                let badge = self.ensure_badge(id)?;
+                self.badge_info.insert(id, &badge);
+            }
+            if end < self.total_badges {
+                return Ok(Some(end));
+            }
+            let version = self.get_storage_version();
+            if version != STORAGE_VERSION {
+                self.env().emit_event(StorageMigrated {
+                    from: version,
+                    to: STORAGE_VERSION,
+                });
+                self.storage_version.insert((), &STORAGE_VERSION);
+            }
+            Ok(None)
+        }
+
+        /// Adds the holders and issuers of a badge written by the original layout to the lists
+        ///
+        /// The caller must be the contract admin. `Mapping`s can't be enumerated on chain, so the
+        /// accounts must be collected off-chain, e.g. from the `issue()` and `add_issuer()` calls.
+        /// The accounts neither holding nor issuing the badge are skipped, and the listed
+        /// accounts are not duplicated.
+        #[ink(message)]
+        pub fn migrate_accounts(&mut self, id: u32, accounts: Vec<AccountId>) -> Result<()> {
+            self.ensure_admin()?;
+            self.ensure_badge(id)?;
+            let mut holders = 0;
+            let mut issuers = 0;
+            for account in accounts {
+                if self.badge_assignments.contains((id, account)) {
+                    self.add_holder(id, account);
+                    holders += 1;
+                }
+                if self.badge_issuers.contains((id, account)) {
+                    issuers += 1;
+                    index_insert(
+                        &mut self.badge_issuer_count,
+                        &mut self.badge_issuer_list,
+                        &mut self.badge_issuer_pos,
+                        id,
+                        account,
+                    );
+                }
+            }
+            self.env().emit_event(AccountsMigrated {
+                id,
+                holders,
+                issuers,
+            });
+            Ok(())
+        }
+
+        /// Imports a page of the state exported from another deployment
+        ///
+        /// The caller must be the contract admin. `snapshot` is a SCALE-encoded
+        /// `Vec<SnapshotEntry>` returned by the `export_*()` queries. The badges and the series
+        /// must be imported first in the order of their ids, followed by the other entries of
+        /// them. Returns an `InvalidSnapshot` error if any entry refers to a missing badge, series
+        /// or code slot, or a badge gets more holders than issued. The revocation records, the
+        /// pending admins and the roles are not exported.
+        ///
+        /// Only allowed on a freshly deployed contract until the import is closed by
+        /// `finish_import()`, `new_badge()` or `new_series()`, which check that the imported state
+        /// is complete.
+        #[ink(message)]
+        pub fn import(&mut self, snapshot: Vec<u8>) -> Result<()> {
+            self.ensure_admin()?;
+            if !self.import_open.contains(()) {
+                return Err(Error::ImportClosed);
+            }
+            let entries: Vec<SnapshotEntry> =
+                Decode::decode(&mut &snapshot[..]).or(Err(Error::InvalidSnapshot))?;
+            // Validate all the entries before writing anything
+            let mut total_badges = self.total_badges;
+            let mut total_series = self.total_series.get(()).unwrap_or(0);
+            let mut series_badges = Vec::new();
+            // The `(num_code, num_issued)` of the badges in this page
+            let mut new_badges = Vec::new();
+            let mut slots = Vec::new();
+            let mut holders = Vec::new();
+            for entry in entries.iter() {
+                let id = match entry {
+                    SnapshotEntry::Badge(badge) if badge.id == total_badges => {
+                        new_badges.push((badge.num_code, badge.num_issued));
+                        total_badges += 1;
+                        continue;
+                    }
+                    SnapshotEntry::Badge(_) => return Err(Error::InvalidSnapshot),
+                    SnapshotEntry::Series(series) if series.id == total_series => {
+                        total_series += 1;
+                        continue;
+                    }
+                    SnapshotEntry::Series(_) => return Err(Error::InvalidSnapshot),
+                    SnapshotEntry::SeriesBadge {
+                        series_id,
+                        badge_id,
+                    } => {
+                        // A badge can be in at most one series
+                        if *series_id >= total_series
+                            || self.badge_series.contains(*badge_id)
+                            || series_badges.contains(badge_id)
+                        {
+                            return Err(Error::InvalidSnapshot);
+                        }
+                        series_badges.push(*badge_id);
+                        *badge_id
+                    }
+                    SnapshotEntry::Assignment {
+                        id, holder, slot, ..
+                    } => {
+                        // A badge can be assigned to a holder only once
+                        if self.badge_assignments.contains((*id, *holder))
+                            || holders.contains(&(*id, *holder))
+                        {
+                            return Err(Error::InvalidSnapshot);
+                        }
+                        holders.push((*id, *holder));
+                        slots.push((*id, *slot));
+                        *id
+                    }
+                    SnapshotEntry::Code { id, slot, .. }
+                    | SnapshotEntry::CodeHash { id, slot, .. }
+                    | SnapshotEntry::SealedCode { id, slot, .. } => {
+                        slots.push((*id, *slot));
+                        *id
+                    }
+                    SnapshotEntry::Issuer { id, .. } | SnapshotEntry::Frozen { id } => *id,
+                };
+                if id >= total_badges {
+                    return Err(Error::InvalidSnapshot);
+                }
+            }
+            let badge_stats = |id: u32| match id.checked_sub(self.total_badges) {
+                Some(i) => new_badges[i as usize],
+                None => {
+                    let badge = self.ensure_badge(id).expect("Badge exists; qed.");
+                    (badge.num_code, badge.num_issued)
+                }
+            };
+            // The slots must exist, and the holders can't outnumber the issued badges
+            if slots.iter().any(|(id, slot)| *slot >= badge_stats(*id).0) {
+                return Err(Error::InvalidSnapshot);
+            }
+            for (id, _) in holders.iter() {
+                let imported = holders.iter().filter(|(i, _)| i == id).count() as u32;
+                let listed = self.badge_holder_count.get(id).unwrap_or(0);
+                if listed + imported > badge_stats(*id).1 {
+                    return Err(Error::InvalidSnapshot);
+                }
+            }
+            let count = entries.len() as u32;
+            for entry in entries {
+                self.import_entry(entry);
+            }
+            self.env().emit_event(SnapshotImported { count });
+            Ok(())
+        }
+
+        /// Closes the import
+        ///
+        /// The caller must be the contract admin. Returns an `InvalidSnapshot` error if the
+        /// imported state is incomplete, i.e. a badge has fewer holders than `num_issued`, or a
+        /// prerequisite of a badge was not imported.
+        #[ink(message)]
+        pub fn finish_import(&mut self) -> Result<()> {
+            self.ensure_admin()?;
+            self.close_import()?;
+            self.env().emit_event(ImportFinished { admin: self.admin });
+            Ok(())
+        }
+
+        // Queries
+
+        /// Exports the badges `[offset, offset + limit)`
+        ///
+        /// The caller must be the contract admin. Returns a SCALE-encoded `Vec<SnapshotEntry>`
+        /// for `import()`, including the freeze state of each badge.
+        #[ink(message)]
+        pub fn export_badges(&self, offset: u32, limit: u32) -> Result<Vec<u8>> {
+            self.ensure_admin()?;
+            let end = offset.saturating_add(limit).min(self.total_badges);
+            let mut entries = Vec::new();
+            for id in offset..end {
+                let badge = self.ensure_badge(id).expect("Badge exists; qed.");
+                entries.push(SnapshotEntry::Badge(badge));
+                if self.badge_frozen.contains(id) {
+                    entries.push(SnapshotEntry::Frozen { id });
+                }
+            }
+            Ok(entries.encode())
+        }
+
+        /// Exports the series `[offset, offset + limit)`
+        ///
+        /// The caller must be the contract admin. Returns a SCALE-encoded `Vec<SnapshotEntry>`
+        /// for `import()`.
+        #[ink(message)]
+        pub fn export_series(&self, offset: u32, limit: u32) -> Result<Vec<u8>> {
+            self.ensure_admin()?;
+            let end = offset
+                .saturating_add(limit)
+                .min(self.total_series.get(()).unwrap_or(0));
+            let entries: Vec<_> = (offset..end)
+                .map(|id| {
+                    SnapshotEntry::Series(self.get_series_info(id).expect("Series exists; qed."))
+                })
+                .collect();
+            Ok(entries.encode())
+        }
+
+        /// Exports a page of the badges in a series
+        ///
+        /// The caller must be the contract admin. Returns a SCALE-encoded `Vec<SnapshotEntry>`
+        /// for `import()`.
+        #[ink(message)]
+        pub fn export_series_badges(&self, id: u32, offset: u32, limit: u32) -> Result<Vec<u8>> {
+            self.ensure_admin()?;
+            self.get_series_info(id)?;
+            let entries: Vec<_> = self
+                .list_series_badges(id, offset, limit)
+                .into_iter()
+                .map(|badge_id| SnapshotEntry::SeriesBadge {
+                    series_id: id,
+                    badge_id,
+                })
+                .collect();
+            Ok(entries.encode())
+        }
+
+        /// Exports a page of the issuers of a badge
+        ///
+        /// The caller must be the contract admin. Returns a SCALE-encoded `Vec<SnapshotEntry>`
+        /// for `import()`.
+        #[ink(message)]
+        pub fn export_issuers(&self, id: u32, offset: u32, limit: u32) -> Result<Vec<u8>> {
+            self.ensure_admin()?;
+            self.ensure_badge(id)?;
+            let entries: Vec<_> = self
+                .list_issuers(id, offset, limit)
+                .into_iter()
+                .map(|issuer| SnapshotEntry::Issuer {
+                    id,
+                    issuer,
+                    allowance: self.badge_issuer_allowance.get((id, issuer)),
+                })
+                .collect();
+            Ok(entries.encode())
+        }
+
+        /// Exports the redeem code slots `[offset, offset + limit)` of a badge
+        ///
+        /// The caller must be the contract admin. Returns a SCALE-encoded `Vec<SnapshotEntry>`
+        /// for `import()`, including the issuance state of each slot and the code sealed at
+        /// upload time.
+        #[ink(message)]
+        pub fn export_code(&self, id: u32, offset: u32, limit: u32) -> Result<Vec<u8>> {
+            self.ensure_admin()?;
+            let badge = self.ensure_badge(id)?;
+            let end = offset.saturating_add(limit).min(badge.num_code);
+            let mut entries = Vec::new();
+            for slot in offset..end {
+                let state = CodeSlotState {
+                    pos: self.pos_of(id, slot),
+                    returned_pos: self.badge_returned_pos.get((id, slot)),
+                    invalid: self.badge_code_invalid.contains((id, slot)),
+                };
+                entries.push(match badge.code_mode {
+                    CodeMode::Plaintext => SnapshotEntry::Code {
+                        id,
+                        slot,
+                        code: self.badge_code.get((id, slot)).expect("Code exists; qed."),
+                        state,
+                    },
+                    CodeMode::Committed => SnapshotEntry::CodeHash {
+                        id,
+                        slot,
+                        hash: self
+                            .badge_code_hash
+                            .get((id, slot))
+                            .expect("Code hash exists; qed."),
+                        state,
+                    },
+                });
+                if let Some(sealed_code) = self.badge_slot_sealed_code.get((id, slot)) {
+                    entries.push(SnapshotEntry::SealedCode {
+                        id,
+                        slot,
+                        sealed_code,
+                    });
+                }
+            }
+            Ok(entries.encode())
+        }
+
+        /// Exports a page of the holders of a badge
+        ///
+        /// The caller must be the contract admin. Returns a SCALE-encoded `Vec<SnapshotEntry>`
+        /// for `import()`.
+        #[ink(message)]
+        pub fn export_assignments(&self, id: u32, offset: u32, limit: u32) -> Result<Vec<u8>> {
+            self.ensure_admin()?;
+            self.ensure_badge(id)?;
+            let entries: Vec<_> = self
+                .list_holders(id, offset, limit)
+                .into_iter()
+                .map(|holder| SnapshotEntry::Assignment {
+                    id,
+                    holder,
+                    slot: self
+                        .badge_assignments
+                        .get((id, holder))
+                        .expect("Listed holder exists; qed."),
+                    issuance: self.badge_issuance.get((id, holder)),
+                    sealed_code: self.badge_sealed_code.get((id, holder)),
+                })
+                .collect();
+            Ok(entries.encode())
+        }
+
+        /// Returns the version of the storage layout
+        #[ink(message)]
+        pub fn get_storage_version(&self) -> u32 {
+            self.storage_version.get(()).unwrap_or(0)
+        }
+
+        /// Returns whether the contract is paused
+        #[ink(message)]
+        pub fn is_paused(&self) -> bool {
+            self.paused.contains(())
+        }
+
+        /// Returns whether a badge is frozen
+        #[ink(message)]
+        pub fn is_badge_frozen(&self, id: u32) -> bool {
+            self.badge_frozen.contains(id)
+        }
+
+        /// Returns the contract admin
+        #[ink(message)]
+        pub fn get_admin(&self) -> AccountId {
+            self.admin
+        }
+
+        /// Returns the pending contract admin proposed by `transfer_ownership()`
+        #[ink(message)]
+        pub fn get_pending_admin(&self) -> Option<AccountId> {
+            self.pending_admin.get(())
+        }
+
+        /// Returns the pending badge admin proposed by `transfer_badge_admin()`
+        #[ink(message)]
+        pub fn get_pending_badge_admin(&self, id: u32) -> Option<AccountId> {
+            self.badge_pending_admin.get(id)
+        }
+
+        /// Returns the number of all the badges
+        #[ink(message)]
+        pub fn get_total_badges(&self) -> u32 {
+            self.total_badges
+        }
+
+        /// Returns the detailed information of a badge
+        #[ink(message)]
+        pub fn get_badge_info(&self, id: u32) -> Result<BadgeInfo> {
+            self.badge_info.get(id).ok_or(Error::BadgeNotFound)
+        }
+
+        /// Returns the number of all the series
+        #[ink(message)]
+        pub fn get_total_series(&self) -> u32 {
+            self.total_series.get(()).unwrap_or(0)
+        }
+
+        /// Returns the series info
+        #[ink(message)]
+        pub fn get_series_info(&self, id: u32) -> Result<SeriesInfo> {
+            self.series_info.get(id).ok_or(Error::SeriesNotFound)
+        }
+
+        /// Returns the series of a badge if it's in one
+        #[ink(message)]
+        pub fn get_badge_series(&self, badge_id: u32) -> Option<u32> {
+            self.badge_series.get(badge_id)
+        }
+
+        /// Lists the ids of the composite badges requiring a badge
+        ///
+        /// Returns at most `limit` badge ids starting from `offset`. The order is not stable when
+        /// the prerequisites are changed.
+        #[ink(message)]
+        pub fn list_dependents(&self, id: u32, offset: u32, limit: u32) -> Vec<u32> {
+            index_page(
+                &self.badge_dependent_count,
+                &self.badge_dependent_list,
+                id,
+                offset,
+                limit,
+            )
+        }
+
+        /// Lists the ids of the badges in a series
+        ///
+        /// Returns at most `limit` badge ids starting from `offset`. The order is not stable when
+        /// badges are removed.
+        #[ink(message)]
+        pub fn list_series_badges(&self, id: u32, offset: u32, limit: u32) -> Vec<u32> {
+            index_page(
+                &self.series_badge_count,
+                &self.series_badge_list,
+                id,
+                offset,
+                limit,
+            )
+        }
+
+        /// Returns how many badges of a series an account holds
+        ///
+        /// Only the valid badges are counted like `has_badge()`.
+        #[ink(message)]
+        pub fn series_progress(&self, id: u32, account: AccountId) -> Result<SeriesProgress> {
+            self.get_series_info(id)?;
+            let total = self.series_badge_count.get(id).unwrap_or(0);
+            let held = self
+                .list_series_badges(id, 0, total)
+                .into_iter()
+                .filter(|badge_id| self.has_badge(*badge_id, account))
+                .count() as u32;
+            Ok(SeriesProgress { held, total })
+        }
+
+        /// Checks if an account is a badge issuer
+        #[ink(message)]
+        pub fn is_badge_issuer(&self, id: u32, issuer: AccountId) -> bool {
+            self.badge_issuers.contains((id, issuer))
+        }
+
+        /// Returns the remaining allowance of a badge issuer
+        ///
+        /// `None` means the issuer can issue unlimited badges.
+        #[ink(message)]
+        pub fn issuer_allowance(&self, id: u32, issuer: AccountId) -> Result<Option<u32>> {
+            if !self.badge_issuers.contains((id, issuer)) {
+                return Err(Error::NotAnIssuer);
+            }
+            Ok(self.badge_issuer_allowance.get((id, issuer)))
+        }
+
+        /// Returns the reason if the badge of an account was revoked
+        #[ink(message)]
+        pub fn get_revocation(&self, id: u32, account: AccountId) -> Option<String> {
+            self.badge_revocations.get((id, account))
+        }
+
+        /// Lists the badges in the order of their ids
+        ///
+        /// Returns at most `limit` badges starting from the id `offset`.
+        #[ink(message)]
+        pub fn list_badges(&self, offset: u32, limit: u32) -> Vec<BadgeInfo> {
+            let end = self.total_badges.min(offset.saturating_add(limit));
+            (offset..end)
+                .map(|id| self.badge_info.get(id).expect("Badge exists; qed."))
+                .collect()
+        }
+
+        /// Lists the issuers of a badge
+        ///
+        /// Returns at most `limit` issuers starting from `offset`. The order is not stable when
+        /// issuers are removed.
+        #[ink(message)]
+        pub fn list_issuers(&self, id: u32, offset: u32, limit: u32) -> Vec<AccountId> {
+            index_page(
+                &self.badge_issuer_count,
+                &self.badge_issuer_list,
+                id,
+                offset,
+                limit,
+            )
+        }
+
+        /// Lists the holders of a badge
+        ///
+        /// Returns at most `limit` holders starting from `offset`. The order is not stable when
+        /// badges are revoked.
+        #[ink(message)]
+        pub fn list_holders(&self, id: u32, offset: u32, limit: u32) -> Vec<AccountId> {
+            index_page(
+                &self.badge_holder_count,
+                &self.badge_holder_list,
+                id,
+                offset,
+                limit,
+            )
+        }
+
+        /// Lists the ids of the badges owned by an account
+        ///
+        /// Returns at most `limit` badge ids starting from `offset`. The order is not stable when
+        /// badges are revoked.
+        #[ink(message)]
+        pub fn badges_of(&self, account: AccountId, offset: u32, limit: u32) -> Vec<u32> {
+            index_page(
+                &self.account_badge_count,
+                &self.account_badge_list,
+                account,
+                offset,
+                limit,
+            )
+        }
+
+        /// Returns the time when the badge was issued to an account
+        #[ink(message)]
+        pub fn get_issued_at(&self, id: u32, account: AccountId) -> Option<Timestamp> {
+            self.get_issuance(id, account)
+                .map(|issuance| issuance.timestamp)
+        }
+
+        /// Returns when and by whom the badge was issued to an account
+        ///
+        /// Returns `None` for the badges issued by the original storage layout.
+        #[ink(message)]
+        pub fn get_issuance(&self, id: u32, account: AccountId) -> Option<Issuance> {
+            self.badge_issuance.get((id, account))
+        }
+
+        /// Returns the redeem code stats of a badge
+        ///
+        /// The caller must be the badge admin or have the `AUDITOR` role.
+        #[ink(message)]
+        pub fn get_code_inventory(&self, id: u32) -> Result<CodeInventory> {
+            let badge = self.ensure_badge_role(id, AUDITOR)?;
+            Ok(CodeInventory {
+                total: badge.num_code,
+                issued: badge.num_issued,
+                available: badge.num_available(),
+                returned: badge.num_returned,
+                invalidated: badge.num_invalid,
+            })
+        }
+
+        /// Returns the index of the code slot assigned to an account
+        #[ink(message)]
+        pub fn get_code_slot(&self, id: u32, account: AccountId) -> Option<u32> {
+            self.badge_assignments.get((id, account))
+        }
+
+        /// Reads the badge code assigned to the caller if exists
+        ///
+        /// Returns a `Revoked` error if the badge of the caller was revoked, an `Expired` error if
+        /// the validity period has passed, or a `WrongCodeMode` error if the badge is in
+        /// `Committed` mode.
+        #[ink(message)]
+        pub fn get(&self, id: u32) -> Result<String> {
+            let caller = self.env().caller();
+            let code_idx = self.ensure_assignment(id, caller)?;
+            if self.ensure_badge(id)?.code_mode != CodeMode::Plaintext {
+                return Err(Error::WrongCodeMode);
+            }
+            let code = self
+                .badge_code
+                .get((id, code_idx))
+                .expect("Assigned code exists; qed.");
+            Ok(code)
+        }
+
+        /// Lists all the valid badges of the caller with the assigned code
+        ///
+        /// Returns the `(id, badge info, code)` triples, where the code is `None` for the badges
+        /// in `Committed` mode. Expired badges are skipped.
+        #[ink(message)]
+        pub fn my_badges(&self) -> Vec<(u32, BadgeInfo, Option<String>)> {
+            let caller = self.env().caller();
+            let count = self.account_badge_count.get(caller).unwrap_or(0);
+            self.badges_of(caller, 0, count)
+                .into_iter()
+                .filter_map(|id| {
+                    let code_idx = self.ensure_assignment(id, caller).ok()?;
+                    let badge = self.ensure_badge(id).ok()?;
+                    let code = match badge.code_mode {
+                        CodeMode::Plaintext => self.badge_code.get((id, code_idx)),
+                        CodeMode::Committed => None,
+                    };
+                    Some((id, badge, code))
+                })
+                .collect()
+        }
+
+        /// Reads the encrypted badge code of the caller
+        ///
+        /// Returns the code delivered by `deliver_code()`, or the code of the assigned slot
+        /// sealed by `add_sealed_code()`. Only available in `Committed` mode. Returns a
+        /// `NotDelivered` error if the code was neither sealed at upload time nor delivered.
+        #[ink(message)]
+        pub fn get_sealed_code(&self, id: u32) -> Result<Vec<u8>> {
+            let caller = self.env().caller();
+            let code_idx = self.ensure_assignment(id, caller)?;
+            if self.ensure_badge(id)?.code_mode != CodeMode::Committed {
+                return Err(Error::WrongCodeMode);
+            }
+            self.badge_sealed_code
+                .get((id, caller))
+                .or_else(|| self.badge_slot_sealed_code.get((id, code_idx)))
+                .ok_or(Error::NotDelivered)
+        }
+
+        /// Checks if `code` matches the hash of the code slot assigned to an account
+        ///
+        /// Only available in `Committed` mode.
+        #[ink(message)]
+        pub fn verify_code(&self, id: u32, account: AccountId, code: String) -> Result<bool> {
+            let code_idx = self.ensure_assignment(id, account)?;
+            if self.ensure_badge(id)?.code_mode != CodeMode::Committed {
+                return Err(Error::WrongCodeMode);
+            }
+            let hash = self
+                .badge_code_hash
+                .get((id, code_idx))
+                .expect("Assigned code hash exists; qed.");
+            Ok(hash == hash_code(&code))
+        }
+
+        // Helper functions
+
+        /// Returns the code slot assigned to the account if exists and not expired
+        fn ensure_assignment(&self, id: u32, account: AccountId) -> Result<u32> {
+            let idx = match self.badge_assignments.get((id, account)) {
+                Some(idx) => idx,
+                None if self.badge_revocations.contains((id, account)) => {
+                    return Err(Error::Revoked)
+                }
+                None => return Err(Error::NotFound),
+            };
+            let badge = self.ensure_badge(id)?;
+            // The badges issued by the original layout have no issuance record and never expire
+            if let (Some(period), Some(issued_at)) =
+                (badge.validity_period, self.get_issued_at(id, account))
+            {
+                if self.env().block_timestamp() >= issued_at.saturating_add(period) {
+                    return Err(Error::Expired);
+                }
+            }
+            Ok(idx)
+        }
+
+        /// Returns the badge info if it exists
+        fn ensure_badge(&self, id: u32) -> Result<BadgeInfo> {
+            self.badge_info.get(id).ok_or(Error::BadgeNotFound)
+        }
+
+        /// Returns `Ok` if the caller is the contract admin
+        fn ensure_admin(&self) -> Result<()> {
+            if self.env().caller() != self.admin {
+                return Err(Error::BadOrigin);
+            }
+            Ok(())
+        }
+
+        /// Returns the badge if the it exists and the caller is the admin
+        ///
+        /// The contract owner can act as the admin of any badge as an emergency override (see
+        /// `roles`).
+        fn ensure_badge_admin(&self, id: u32) -> Result<BadgeInfo> {
+            let caller = self.env().caller();
+            let badge = self.badge_info.get(id).ok_or(Error::BadgeNotFound)?;
+            if badge.admin != caller && self.admin != caller {
+                return Err(Error::BadOrigin);
+            }
+            Ok(badge)
+        }
+
+        /// Appends the committed code, optionally sealed, to a badge
+        fn append_committed_code(
+            &mut self,
+            id: u32,
+            code: Vec<([u8; 32], Option<Vec<u8>>)>,
+        ) -> Result<()> {
+            let mut badge = self.ensure_badge_role(id, CODE_UPLOADER)?;
+            self.ensure_active(id)?;
+            if badge.code_mode != CodeMode::Committed {
+                return Err(Error::WrongCodeMode);
+            }
+            let start = badge.num_code;
+            badge.num_code += code.len() as u32;
+            for (i, (hash, sealed_code)) in code.iter().enumerate() {
+                let idx = (i as u32) + start;
+                self.badge_code_hash.insert((id, idx), hash);
+                if let Some(sealed_code) = sealed_code {
+                    self.badge_slot_sealed_code.insert((id, idx), sealed_code);
+                }
+            }
+            self.badge_info.insert(id, &badge);
+            self.env().emit_event(CodeAdded {
+                id,
+                count: code.len() as u32,
+            });
+            Ok(())
+        }
+
+        /// Returns the badge if the caller is the badge admin, the badge is in `mode`, and the code
+        /// slot is unissued
+        fn ensure_replaceable_code(&self, id: u32, slot: u32, mode: CodeMode) -> Result<BadgeInfo> {
+            let badge = self.ensure_badge_admin(id)?;
+            if badge.code_mode != mode {
+                return Err(Error::WrongCodeMode);
+            }
+            if !self.is_unissued(&badge, slot) {
+                return Err(Error::InvalidCodeSlot);
+            }
+            Ok(badge)
+        }
+
+        /// Makes a replaced code slot valid again
+        fn revalidate_code(&mut self, mut badge: BadgeInfo, slot: u32) {
+            let id = badge.id;
+            if self.badge_code_invalid.contains((id, slot)) {
+                self.badge_code_invalid.remove((id, slot));
+                badge.num_invalid -= 1;
+                self.badge_info.insert(id, &badge);
+            }
+            self.env().emit_event(CodeReplaced { id, slot });
+        }
+
+        /// Writes an entry exported by another deployment
+        fn import_entry(&mut self, entry: SnapshotEntry) {
+            match entry {
+                SnapshotEntry::Badge(badge) => {
+                    for prerequisite in badge.prerequisites.iter() {
+                        index_insert(
+                            &mut self.badge_dependent_count,
+                            &mut self.badge_dependent_list,
+                            &mut self.badge_dependent_pos,
+                            *prerequisite,
+                            badge.id,
+                        );
+                    }
+                    self.badge_info.insert(badge.id, &badge);
+                    self.total_badges += 1;
+                }
+                SnapshotEntry::Issuer {
+                    id,
+                    issuer,
+                    allowance,
+                } => self.insert_issuer(id, issuer, allowance),
+                SnapshotEntry::Code {
+                    id,
+                    slot,
+                    code,
+                    state,
+                } => {
+                    self.badge_code.insert((id, slot), &code);
+                    self.import_code_slot_state(id, slot, state);
+                }
+                SnapshotEntry::CodeHash {
+                    id,
+                    slot,
+                    hash,
+                    state,
+                } => {
+                    self.badge_code_hash.insert((id, slot), &hash);
+                    self.import_code_slot_state(id, slot, state);
+                }
+                SnapshotEntry::Assignment {
+                    id,
+                    holder,
+                    slot,
+                    issuance,
+                    sealed_code,
+                } => {
+                    self.badge_assignments.insert((id, holder), &slot);
+                    self.add_holder(id, holder);
+                    if let Some(issuance) = issuance {
+                        self.badge_issuance.insert((id, holder), &issuance);
+                    }
+                    if let Some(sealed_code) = sealed_code {
+                        self.badge_sealed_code.insert((id, holder), &sealed_code);
+                    }
+                }
+                SnapshotEntry::Frozen { id } => self.badge_frozen.insert(id, &()),
+                SnapshotEntry::Series(series) => {
+                    self.series_info.insert(series.id, &series);
+                    self.total_series.insert((), &(series.id + 1));
+                }
+                SnapshotEntry::SeriesBadge {
+                    series_id,
+                    badge_id,
+                } => {
+                    self.badge_series.insert(badge_id, &series_id);
+                    index_insert(
+                        &mut self.series_badge_count,
+                        &mut self.series_badge_list,
+                        &mut self.series_badge_pos,
+                        series_id,
+                        badge_id,
+                    );
+                }
+                SnapshotEntry::SealedCode {
+                    id,
+                    slot,
+                    sealed_code,
+                } => self.badge_slot_sealed_code.insert((id, slot), &sealed_code),
+            }
+        }
+
+        /// Closes the import if it's still open, checking the imported state is complete
+        fn close_import(&mut self) -> Result<()> {
+            if !self.import_open.contains(()) {
+                return Ok(());
+            }
+            for id in 0..self.total_badges {
+                let badge = self.ensure_badge(id)?;
+                if self.badge_holder_count.get(id).unwrap_or(0) != badge.num_issued
+                    || badge.prerequisites.iter().any(|p| *p >= self.total_badges)
+                {
+                    return Err(Error::InvalidSnapshot);
+                }
+            }
+            self.import_open.remove(());
+            Ok(())
+        }
+
+        /// Restores the issuance state of a code slot
+        fn import_code_slot_state(&mut self, id: u32, slot: u32, state: CodeSlotState) {
+            if state.pos != slot {
+                self.badge_code_perm.insert((id, state.pos), &slot);
+                self.badge_code_perm_inv.insert((id, slot), &state.pos);
+            }
+            if let Some(pos) = state.returned_pos {
+                self.badge_returned_code.insert((id, pos), &slot);
+                self.badge_returned_pos.insert((id, slot), &pos);
+            }
+            if state.invalid {
+                self.badge_code_invalid.insert((id, slot), &());
+            }
+        }
+
+        /// Returns `Ok` if neither the contract is paused nor the badge is frozen
+        fn ensure_active(&self, id: u32) -> Result<()> {
+            if self.paused.contains(()) {
+                return Err(Error::Paused);
+            }
+            if self.badge_frozen.contains(id) {
+                return Err(Error::Frozen);
+            }
+            Ok(())
+        }
+
+        /// Sets the pause state of the contract
+        fn set_paused(&mut self, paused: bool) -> Result<()> {
+            self.ensure_admin()?;
+            if paused {
+                self.paused.insert((), &());
+            } else {
+                self.paused.remove(());
+            }
+            self.env().emit_event(PauseChanged {
+                admin: self.admin,
+                paused,
+            });
+            Ok(())
+        }
+
+        /// Returns the series if the it exists and the caller is the admin
+        ///
+        /// The contract owner can act as the admin of any series.
+        fn ensure_series_admin(&self, id: u32) -> Result<SeriesInfo> {
+            let caller = self.env().caller();
+            let series = self.get_series_info(id)?;
+            if series.admin != caller && self.admin != caller {
+                return Err(Error::BadOrigin);
+            }
+            Ok(series)
+        }
+
+        /// Returns the badge if the it exists and the caller is the admin or has the role
+        fn ensure_badge_role(&self, id: u32, role: RoleType) -> Result<BadgeInfo> {
+            let caller = self.env().caller();
+            let badge = self.badge_info.get(id).ok_or(Error::BadgeNotFound)?;
+            if badge.admin != caller && !self.has_role(role, caller) {
+                return Err(Error::BadOrigin);
+            }
+            Ok(badge)
+        }
+
+        /// Returns `Ok` if the caller can manage the role
+        ///
+        /// The caller must have the admin role of the role, or be the contract owner.
+        fn ensure_role_admin(
+            &self,
+            role: RoleType,
+        ) -> core::result::Result<(), AccessControlError> {
+            let caller = self.env().caller();
+            if caller != self.admin && !self.has_role(self.get_role_admin(role), caller) {
+                return Err(AccessControlError::MissingRole);
+            }
+            // The owner is changed by `transfer_ownership()` only
+            if role == OWNER {
+                return Err(AccessControlError::InvalidCaller);
+            }
+            Ok(())
+        }
+
+        /// Adds or updates a badge issuer with its allowance
+        fn insert_issuer(&mut self, id: u32, issuer: AccountId, allowance: Option<u32>) {
+            self.badge_issuers.insert((id, issuer), &());
+            match allowance {
+                Some(allowance) => self.badge_issuer_allowance.insert((id, issuer), &allowance),
+                None => self.badge_issuer_allowance.remove((id, issuer)),
+            }
+            index_insert(
+                &mut self.badge_issuer_count,
+                &mut self.badge_issuer_list,
+                &mut self.badge_issuer_pos,
+                id,
+                issuer,
+            );
+        }
+
+        /// Adds the account to the holder indexes of a badge
+        fn add_holder(&mut self, id: u32, account: AccountId) {
+            index_insert(
+                &mut self.badge_holder_count,
+                &mut self.badge_holder_list,
+                &mut self.badge_holder_pos,
+                id,
+                account,
+            );
+            index_insert(
+                &mut self.account_badge_count,
+                &mut self.account_badge_list,
+                &mut self.account_badge_pos,
+                account,
+                id,
+            );
+        }
+
+        /// Removes the account from the holder indexes of a badge
+        fn remove_holder(&mut self, id: u32, account: AccountId) {
+            index_remove(
+                &mut self.badge_holder_count,
+                &mut self.badge_holder_list,
+                &mut self.badge_holder_pos,
+                id,
+                account,
+            );
+            index_remove(
+                &mut self.account_badge_count,
+                &mut self.account_badge_list,
+                &mut self.account_badge_pos,
+                account,
+                id,
+            );
+        }
+
+        /// Returns `Ok` if the caller can issue the badge now
+        ///
+        /// The caller must be a badge issuer, the badge admin or have the `ISSUER` role, the
+        /// current time must be in the issuance window, and the badge must not be paused or
+        /// frozen.
+        fn ensure_issuable(&self, badge: &BadgeInfo) -> Result<()> {
+            let caller = self.env().caller();
+            if !self.badge_issuers.contains((badge.id, caller))
+                && caller != badge.admin
+                && !self.has_role(ISSUER, caller)
+            {
+                return Err(Error::NotAnIssuer);
+            }
+            self.ensure_issuance_open(badge)
+        }
+
+        /// Returns `Ok` if the badge is not paused or frozen, and the current time is in the
+        /// issuance window
+        fn ensure_issuance_open(&self, badge: &BadgeInfo) -> Result<()> {
+            self.ensure_active(badge.id)?;
+            let now = self.env().block_timestamp();
+            if matches!(badge.start_time, Some(start) if now < start) {
+                return Err(Error::NotStarted);
+            }
+            if matches!(badge.end_time, Some(end) if now >= end) {
+                return Err(Error::Ended);
+            }
+            Ok(())
+        }
+
+        /// Assigns a redeem code of the badge to `dest`
+        ///
+        /// Updates `badge` in place. The caller is responsible to save it. The allowance of
+        /// `issuer` is consumed if it's not `None`, otherwise the contract itself is recorded as
+        /// the issuer.
+        fn assign_badge(
+            &mut self,
+            badge: &mut BadgeInfo,
+            dest: AccountId,
+            issuer: Option<AccountId>,
+        ) -> Result<()> {
+            let id = badge.id;
+            // Check the supply cap
+            if matches!(badge.max_supply, Some(max) if badge.num_issued >= max) {
+                return Err(Error::SupplyCapReached);
+            }
+            // Make sure we don't issue more than what we have
+            if badge.num_available() == 0 {
+                return Err(Error::RunOutOfCode);
+            }
+            // No duplication
+            if self.badge_assignments.contains((id, dest)) {
                 return Err(Error::Duplicated);
             }
-            // Update assignment and issued count
-            let idx = badge.num_issued;
-            self.badge_assignments.insert((id, dest), &idx);
-            badge.num_issued += 1;
-            self.badge_info.insert(id, &badge);
-            Ok(())
+            // Consume the allowance of the issuer (the badge admin and the `ISSUER` role have no
+            // limit)
+            if let Some(issuer) =
+                issuer.filter(|issuer| *issuer != badge.admin && !self.has_role(ISSUER, *issuer))
+            {
+                if let Some(allowance) = self.badge_issuer_allowance.get((id, issuer)) {
+                    if allowance == 0 {
+                        return Err(Error::QuotaExceeded);
+                    }
+                    self.badge_issuer_allowance
+                        .insert((id, issuer), &(allowance - 1));
+                }
+            }
+            // Update assignment and issued count
+            let idx = self.take_code(badge);
+            let issuance = Issuance {
+                timestamp: self.env().block_timestamp(),
+                block_number: self.env().block_number(),
+                issuer: issuer.unwrap_or_else(|| self.env().account_id()),
+            };
+            self.badge_assignments.insert((id, dest), &idx);
+            self.add_holder(id, dest);
+            self.badge_issuance.insert((id, dest), &issuance);
+            self.badge_revocations.remove((id, dest));
+            badge.num_issued += 1;
+            self.env().emit_event(BadgeIssued { id, dest });
+            Ok(())
+        }
+
+        /// Issues the composite badges `account` qualifies for after receiving badge `id`
+        ///
+        /// It's best effort. A composite badge is skipped if it's not issuable now, e.g. for
+        /// `RunOutOfCode`, `Duplicated` or `Frozen`. The issuer allowance is not consumed. The
+        /// newly issued composite badges may qualify the account for more composite badges.
+        fn issue_composites(&mut self, id: u32, account: AccountId) {
+            let mut queue = Vec::from([id]);
+            while let Some(id) = queue.pop() {
+                let count = self.badge_dependent_count.get(id).unwrap_or(0);
+                for dependent in self.list_dependents(id, 0, count) {
+                    if self.issue_composite(dependent, account).is_ok() {
+                        queue.push(dependent);
+                    }
+                }
+            }
+        }
+
+        /// Issues a composite badge if `account` holds all the prerequisites
+        fn issue_composite(&mut self, id: u32, account: AccountId) -> Result<()> {
+            let mut badge = self.ensure_badge(id)?;
+            if badge.prerequisites.is_empty()
+                || !badge
+                    .prerequisites
+                    .iter()
+                    .all(|prerequisite| self.has_badge(*prerequisite, account))
+            {
+                return Err(Error::NotFound);
+            }
+            self.ensure_issuance_open(&badge)?;
+            self.assign_badge(&mut badge, account, None)?;
+            self.badge_info.insert(id, &badge);
+            Ok(())
+        }
+
+        /// Checks if `id` is reachable from the badges following their prerequisites
+        fn depends_on(&self, badges: &[u32], id: u32) -> bool {
+            let mut visited = Vec::new();
+            let mut stack = badges.to_vec();
+            while let Some(badge) = stack.pop() {
+                if badge == id {
+                    return true;
+                }
+                if visited.contains(&badge) {
+                    continue;
+                }
+                visited.push(badge);
+                if let Ok(info) = self.ensure_badge(badge) {
+                    stack.extend(info.prerequisites);
+                }
+            }
+            false
+        }
+
+        /// Takes a random unissued redeem code to issue
+        ///
+        /// The candidates are the returned code and the code never drawn before. The latter is
+        /// drawn by a lazy Fisher-Yates shuffle: the positions `[next_code, num_code)` hold the
+        /// remaining slots, and only the positions swapped away from their own slots are stored
+        /// in `badge_code_perm`. Invalidated code is dropped when drawn.
+        ///
+        /// The caller must make sure `badge.num_available() > 0`.
+        fn take_code(&mut self, badge: &mut BadgeInfo) -> u32 {
+            loop {
+                let num_fresh = badge.num_code - badge.next_code;
+                let r = self.random_below(badge, badge.num_returned + num_fresh);
+                let slot = if r < badge.num_returned {
+                    self.take_returned_code(badge, r)
+                } else {
+                    self.take_fresh_code(badge, badge.next_code + r - badge.num_returned)
+                };
+                if !self.badge_code_invalid.contains((badge.id, slot)) {
+                    return slot;
+                }
+                badge.num_invalid -= 1;
+            }
+        }
+
+        /// Takes the returned code at `pos` by moving the last returned code to its position
+        fn take_returned_code(&mut self, badge: &mut BadgeInfo, pos: u32) -> u32 {
+            let id = badge.id;
+            badge.num_returned -= 1;
+            let last = badge.num_returned;
+            let slot = self
+                .badge_returned_code
+                .get((id, pos))
+                .expect("Returned code exists; qed.");
+            if pos != last {
+                let last_slot = self
+                    .badge_returned_code
+                    .get((id, last))
+                    .expect("Returned code exists; qed.");
+                self.badge_returned_code.insert((id, pos), &last_slot);
+                self.badge_returned_pos.insert((id, last_slot), &pos);
+            }
+            self.badge_returned_code.remove((id, last));
+            self.badge_returned_pos.remove((id, slot));
+            slot
+        }
+
+        /// Takes the never drawn code at `pos` by swapping it with the code at `next_code`
+        fn take_fresh_code(&mut self, badge: &mut BadgeInfo, pos: u32) -> u32 {
+            let id = badge.id;
+            let head = badge.next_code;
+            let slot = self.slot_at(id, pos);
+            let head_slot = self.slot_at(id, head);
+            self.badge_code_perm.insert((id, pos), &head_slot);
+            self.badge_code_perm_inv.insert((id, head_slot), &pos);
+            self.badge_code_perm.insert((id, head), &slot);
+            self.badge_code_perm_inv.insert((id, slot), &head);
+            badge.next_code += 1;
+            slot
+        }
+
+        /// Returns the code slot at a position of the shuffled code
+        fn slot_at(&self, id: u32, pos: u32) -> u32 {
+            self.badge_code_perm.get((id, pos)).unwrap_or(pos)
+        }
+
+        /// Returns the position of a code slot in the shuffled code
+        fn pos_of(&self, id: u32, slot: u32) -> u32 {
+            self.badge_code_perm_inv.get((id, slot)).unwrap_or(slot)
+        }
+
+        /// Checks if a code slot exists and can still be issued
+        fn is_unissued(&self, badge: &BadgeInfo, slot: u32) -> bool {
+            slot < badge.num_code
+                && (self.pos_of(badge.id, slot) >= badge.next_code
+                    || self.badge_returned_pos.contains((badge.id, slot)))
+        }
+
+        /// Returns a uniformly random number in `[0, n)`
+        ///
+        /// The random numbers below `2^32 % n` are rejected, so that each result is mapped from
+        /// the same number of random numbers without the modulo bias.
+        fn random_below(&self, badge: &BadgeInfo, n: u32) -> u32 {
+            let threshold = n.wrapping_neg() % n;
+            let mut nonce = 0;
+            loop {
+                let r = self.random_u32(badge, nonce);
+                if r >= threshold {
+                    return r % n;
+                }
+                nonce += 1;
+            }
+        }
+
+        /// Returns a random number from the secure randomness of Phala
+        #[cfg(all(feature = "pink", not(test)))]
+        fn random_u32(&self, _badge: &BadgeInfo, _nonce: u32) -> u32 {
+            let bytes = pink_extension::ext().getrandom(4);
+            u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+        }
+
+        /// Returns a pseudo random number derived from the block and the badge stats
+        ///
+        /// It's deterministic and predictable by anyone reading the chain. Only used in the unit
+        /// tests and the builds without the `pink` feature, e.g. a deployment on a chain without
+        /// the Phala randomness. `nonce` distinguishes the draws in the same state.
+        #[cfg(any(not(feature = "pink"), test))]
+        fn random_u32(&self, badge: &BadgeInfo, nonce: u32) -> u32 {
+            use ink_env::hash::{Blake2x256, HashOutput};
+            let input = (
+                self.env().block_number(),
+                self.env().block_timestamp(),
+                badge.id,
+                badge.num_issued,
+                badge.next_code,
+                badge.num_returned,
+                badge.num_invalid,
+                nonce,
+            )
+                .encode();
+            let mut output = <Blake2x256 as HashOutput>::Type::default();
+            ink_env::hash_bytes::<Blake2x256>(&input, &mut output);
+            u32::from_le_bytes([output[0], output[1], output[2], output[3]])
+        }
+    }
+
+    /// Hashes a redeem code for `CodeMode::Committed`
+    fn hash_code(code: &str) -> [u8; 32] {
+        use ink_env::hash::{Blake2x256, HashOutput};
+        let mut output = <Blake2x256 as HashOutput>::Type::default();
+        ink_env::hash_bytes::<Blake2x256>(code.as_bytes(), &mut output);
+        output
+    }
+
+    /// The placeholder admin of a renounced contract or badge
+    fn zero_account() -> AccountId {
+        AccountId::from([0u8; 32])
+    }
+
+    // Secondary indexes
+    //
+    // An index is a list of values under a scope (e.g. the issuers of a badge), stored in three
+    // mappings: the length of the list, the values by position, and the positions by value.
+
+    /// Appends `value` to the list under `scope` unless it's already there
+    fn index_insert<S, V>(
+        count: &mut Mapping<S, u32>,
+        list: &mut Mapping<(S, u32), V>,
+        pos: &mut Mapping<(S, V), u32>,
+        scope: S,
+        value: V,
+    ) where
+        S: Encode + Copy,
+        V: PackedLayout + Copy,
+    {
+        if pos.contains((scope, value)) {
+            return;
+        }
+        let len = count.get(scope).unwrap_or(0);
+        list.insert((scope, len), &value);
+        pos.insert((scope, value), &len);
+        count.insert(scope, &(len + 1));
+    }
+
+    /// Removes `value` from the list under `scope` by moving the last value to its position
+    fn index_remove<S, V>(
+        count: &mut Mapping<S, u32>,
+        list: &mut Mapping<(S, u32), V>,
+        pos: &mut Mapping<(S, V), u32>,
+        scope: S,
+        value: V,
+    ) where
+        S: Encode + Copy,
+        V: PackedLayout + Copy,
+    {
+        let idx = match pos.get((scope, value)) {
+            Some(idx) => idx,
+            None => return,
+        };
+        let last = count.get(scope).expect("Non-empty list has length; qed.") - 1;
+        if idx != last {
+            let last_value = list.get((scope, last)).expect("List item exists; qed.");
+            list.insert((scope, idx), &last_value);
+            pos.insert((scope, last_value), &idx);
+        }
+        list.remove((scope, last));
+        pos.remove((scope, value));
+        count.insert(scope, &last);
+    }
+
+    /// Returns at most `limit` values of the list under `scope` starting from `offset`
+    fn index_page<S, V>(
+        count: &Mapping<S, u32>,
+        list: &Mapping<(S, u32), V>,
+        scope: S,
+        offset: u32,
+        limit: u32,
+    ) -> Vec<V>
+    where
+        S: Encode + Copy,
+        V: PackedLayout,
+    {
+        let len = count.get(scope).unwrap_or(0);
+        let end = len.min(offset.saturating_add(limit));
+        (offset..end)
+            .map(|i| list.get((scope, i)).expect("List item exists; qed."))
+            .collect()
+    }
+
+    impl Issuable for FatBadges {
+        /// Issues a badge to the `dest` account
+        ///
+        /// The caller must be the badge admin, a badge issuer, or have the `ISSUER` role. Return a
+        /// `RunOutOfCode` error when there's no enough redeem code to issue, or a `QuotaExceeded`
+        /// error when the issuer has used up its allowance. A revoked account can be issued again.
+        ///
+        /// The issuance rules set by `set_issuance_rules()` are checked against the current block
+        /// timestamp. Then the composite badges `dest` qualifies for are issued as well.
+        #[ink(message)]
+        fn issue(&mut self, id: u32, dest: AccountId) -> Result<()> {
+            let mut badge = self.ensure_badge(id)?;
+            self.ensure_issuable(&badge)?;
+            let caller = self.env().caller();
+            self.assign_badge(&mut badge, dest, Some(caller))?;
+            self.badge_info.insert(id, &badge);
+            self.issue_composites(id, dest);
+            Ok(())
+        }
+
+        /// Issues a badge to a list of accounts
+        ///
+        /// The same as calling `issue()` with each account in `dest`, but the badge stats are
+        /// only updated once. Returns an error if the caller cannot issue the badge at all.
+        /// Otherwise returns the result of each account in the same order as `dest`, e.g.
+        /// `Duplicated` or `RunOutOfCode`.
+        #[ink(message)]
+        fn issue_batch(&mut self, id: u32, dest: Vec<AccountId>) -> Result<Vec<Result<()>>> {
+            let mut badge = self.ensure_badge(id)?;
+            self.ensure_issuable(&badge)?;
+            let caller = self.env().caller();
+            let results: Vec<_> = dest
+                .iter()
+                .map(|account| self.assign_badge(&mut badge, *account, Some(caller)))
+                .collect();
+            self.badge_info.insert(id, &badge);
+            for (account, result) in dest.into_iter().zip(results.iter()) {
+                if result.is_ok() {
+                    self.issue_composites(id, account);
+                }
+            }
+            Ok(results)
+        }
+    }
+
+    /// The openbrush `AccessControl` interface on the contract's own role storage
+    ///
+    /// The default implementation of openbrush is not used because it keeps the roles in its own
+    /// storage field, while this contract only appends `Mapping`s to the original layout (see
+    /// `STORAGE_VERSION`), and the owner is managed by `transfer_ownership()` instead of a role.
+    impl AccessControl for FatBadges {
+        /// Checks if an account has a role
+        ///
+        /// The contract owner has `OWNER` and the roles in `OWNER_ROLES` implicitly, but not the
+        /// roles unknown to the contract.
+        #[ink(message)]
+        fn has_role(&self, role: RoleType, address: AccountId) -> bool {
+            let implicit = role == OWNER || OWNER_ROLES.contains(&role);
+            (implicit && address == self.admin) || self.roles.contains((role, address))
+        }
+
+        /// Returns the admin role of a role set by `set_role_admin()`, which is `OWNER` by default
+        #[ink(message)]
+        fn get_role_admin(&self, role: RoleType) -> RoleType {
+            self.role_admins.get(role).unwrap_or(OWNER)
+        }
+
+        /// Grants a role to an account
+        ///
+        /// The caller must have the admin role of the role, or be the contract owner. The `OWNER`
+        /// role can't be granted.
+        #[ink(message)]
+        fn grant_role(
+            &mut self,
+            role: RoleType,
+            account: AccountId,
+        ) -> core::result::Result<(), AccessControlError> {
+            self.ensure_role_admin(role)?;
+            if self.roles.contains((role, account)) {
+                return Err(AccessControlError::RoleRedundant);
+            }
+            self.roles.insert((role, account), &());
+            self.env().emit_event(RoleGranted {
+                role,
+                grantee: account,
+                grantor: self.env().caller(),
+            });
+            Ok(())
+        }
+
+        /// Revokes a role from an account
+        ///
+        /// The caller must have the admin role of the role, or be the contract owner.
+        #[ink(message)]
+        fn revoke_role(
+            &mut self,
+            role: RoleType,
+            account: AccountId,
+        ) -> core::result::Result<(), AccessControlError> {
+            self.ensure_role_admin(role)?;
+            if !self.roles.contains((role, account)) {
+                return Err(AccessControlError::MissingRole);
+            }
+            self.roles.remove((role, account));
+            self.env().emit_event(RoleRevoked {
+                role,
+                account,
+                sender: self.env().caller(),
+            });
+            Ok(())
+        }
+
+        /// Gives up a role of the caller
+        ///
+        /// `account` must be the caller.
+        #[ink(message)]
+        fn renounce_role(
+            &mut self,
+            role: RoleType,
+            account: AccountId,
+        ) -> core::result::Result<(), AccessControlError> {
+            if self.env().caller() != account {
+                return Err(AccessControlError::InvalidCaller);
+            }
+            if !self.roles.contains((role, account)) {
+                return Err(AccessControlError::MissingRole);
+            }
+            self.roles.remove((role, account));
+            self.env().emit_event(RoleRevoked {
+                role,
+                account,
+                sender: account,
+            });
+            Ok(())
+        }
+    }
+
+    impl BadgeQuery for FatBadges {
+        /// Checks if an account holds a valid badge
+        ///
+        /// Only reveals the membership but not the code. Revoked or expired badges don't count.
+        #[ink(message)]
+        fn has_badge(&self, id: u32, account: AccountId) -> bool {
+            self.ensure_assignment(id, account).is_ok()
+        }
+
+        /// Returns the number of badges held by an account, including the expired ones
+        #[ink(message)]
+        fn badge_count_of(&self, account: AccountId) -> u32 {
+            self.account_badge_count.get(account).unwrap_or(0)
+        }
+
+        /// Returns the time when the badge was issued to an account
+        #[ink(message)]
+        fn issued_at(&self, id: u32, account: AccountId) -> Option<Timestamp> {
+            self.get_issued_at(id, account)
+        }
+    }
+
+    /// A read-only PSP34 view of the badge holdings
+    ///
+    /// Each (badge id, holder) assignment is a soulbound token with the id
+    /// `Id::Bytes((badge_id, holder).encode())`. Tokens can't be approved or transferred.
+    impl PSP34 for FatBadges {
+        #[ink(message)]
+        fn collection_id(&self) -> Id {
+            Id::Bytes(self.env().account_id().encode())
+        }
+
+        /// Returns the number of badges held by `owner`
+        #[ink(message)]
+        fn balance_of(&self, owner: AccountId) -> u32 {
+            self.account_badge_count.get(owner).unwrap_or(0)
+        }
+
+        #[ink(message)]
+        fn owner_of(&self, id: Id) -> Option<AccountId> {
+            let (badge_id, holder) = decode_token_id(&id)?;
+            if self.badge_assignments.contains((badge_id, holder)) {
+                Some(holder)
+            } else {
+                None
+            }
+        }
+
+        #[ink(message)]
+        fn allowance(&self, _owner: AccountId, _operator: AccountId, _id: Option<Id>) -> bool {
+            false
+        }
+
+        #[ink(message)]
+        fn approve(
+            &mut self,
+            _operator: AccountId,
+            _id: Option<Id>,
+            _approved: bool,
+        ) -> core::result::Result<(), PSP34Error> {
+            Err(PSP34Error::Custom(String::from("Soulbound")))
+        }
+
+        #[ink(message)]
+        fn transfer(
+            &mut self,
+            _to: AccountId,
+            _id: Id,
+            _data: Vec<u8>,
+        ) -> core::result::Result<(), PSP34Error> {
+            Err(PSP34Error::Custom(String::from("Soulbound")))
+        }
+
+        /// Returns the number of badges held by all the accounts
+        #[ink(message)]
+        fn total_supply(&self) -> Balance {
+            (0..self.total_badges)
+                .filter_map(|id| self.badge_info.get(id))
+                .map(|badge| badge.num_issued as Balance)
+                .sum()
+        }
+    }
+
+    impl PSP34Metadata for FatBadges {
+        /// Returns the attribute of the badge behind a token
+        ///
+        /// Supported keys: `name`, `description`, `image`, `external_link`, and the keys of
+        /// the attributes in the badge metadata.
+        #[ink(message)]
+        fn get_attribute(&self, id: Id, key: Vec<u8>) -> Option<Vec<u8>> {
+            let (badge_id, holder) = decode_token_id(&id)?;
+            if !self.badge_assignments.contains((badge_id, holder)) {
+                return None;
+            }
+            let badge = self.badge_info.get(badge_id)?;
+            let metadata = badge.metadata;
+            match &key[..] {
+                b"name" => Some(badge.name.into_bytes()),
+                b"description" => Some(metadata.description.into_bytes()),
+                b"image" => Some(metadata.image_uri.into_bytes()),
+                b"external_link" => Some(metadata.external_link.into_bytes()),
+                _ => metadata
+                    .attributes
+                    .into_iter()
+                    .find(|(k, _)| k.as_bytes() == &key[..])
+                    .map(|(_, v)| v.into_bytes()),
+            }
+        }
+    }
+
+    /// Decodes the badge id and the holder from a PSP34 token id
+    fn decode_token_id(id: &Id) -> Option<(u32, AccountId)> {
+        match id {
+            Id::Bytes(bytes) => Decode::decode(&mut &bytes[..]).ok(),
+            _ => None,
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use ink_lang as ink;
+        use openbrush::traits::mock::{Addressable, SharedCallStack};
+
+        type Event = <FatBadges as ::ink_lang::reflect::ContractEventBase>::Type;
+
+        fn default_accounts() -> ink_env::test::DefaultAccounts<ink_env::DefaultEnvironment> {
+            ink_env::test::default_accounts::<Environment>()
+        }
+
+        fn recorded_events() -> Vec<Event> {
+            ink_env::test::recorded_events()
+                .map(|e| <Event as Decode>::decode(&mut &e.data[..]).expect("Invalid event data"))
+                .collect()
+        }
+
+        /// Returns the PSP34 token id of a badge assignment
+        fn token_id(id: u32, holder: AccountId) -> Id {
+            Id::Bytes((id, holder).encode())
+        }
+
+        #[ink::test]
+        fn issue_badges() {
+            let accounts = default_accounts();
+
+            let stack = SharedCallStack::new(accounts.alice);
+            let fat_badges = Addressable::create_native(1, FatBadges::new(), stack.clone());
+            assert_eq!(fat_badges.call().admin, accounts.alice);
+
+            // Alice can create a badge
+            let id = fat_badges
+                .call_mut()
+                .new_badge("Phala Workshop: Easy".to_string())
+                .expect("Should be able to create badges");
+
+            // Can add an issuer
+            assert!(fat_badges
+                .call_mut()
+                .add_issuer(id, accounts.bob, None)
+                .is_ok());
+
+            // Bob can create another badge after granted the creator role
+            stack.switch_account(accounts.bob).unwrap();
+            assert_eq!(
+                fat_badges
+                    .call_mut()
+                    .new_badge("Phala Workshop: Advanced".to_string()),
+                Err(Error::BadOrigin)
+            );
+            stack.switch_account(accounts.alice).unwrap();
+            assert!(fat_badges
+                .call_mut()
+                .grant_role(BADGE_CREATOR, accounts.bob)
+                .is_ok());
+            stack.switch_account(accounts.bob).unwrap();
+            let id_adv = fat_badges
+                .call_mut()
+                .new_badge("Phala Workshop: Advanced".to_string())
+                .expect("Should be able to create badges");
+            stack.switch_account(accounts.charlie).unwrap();
+            assert_eq!(
+                fat_badges.call_mut().add_issuer(id_adv, accounts.bob, None),
+                Err(Error::BadOrigin),
+                "Only the badge owner can add issuers"
+            );
+            assert_eq!(
+                fat_badges.call_mut().add_issuer(999, accounts.bob, None),
+                Err(Error::BadgeNotFound),
+                "Non-existing badge"
+            );
+            stack.switch_account(accounts.alice).unwrap();
+
+            // Can remove an issuer
+            assert!(fat_badges
+                .call_mut()
+                .add_issuer(id, accounts.charlie, None)
+                .is_ok());
+            assert!(fat_badges
+                .call_mut()
+                .remove_issuer(id, accounts.charlie)
+                .is_ok());
+            assert!(!fat_badges.call().is_badge_issuer(id, accounts.charlie));
+
+            // Can add code
+            assert!(fat_badges
+                .call_mut()
+                .add_code(id, vec!["code1".to_string(), "code2".to_string()])
+                .is_ok());
+            stack.switch_account(accounts.bob).unwrap();
+            assert_eq!(
+                fat_badges.call_mut().add_code(id, vec![]),
+                Err(Error::BadOrigin),
+                "Only the badge owner can add code"
+            );
+
+            // Check the badge stats
+            let badge = fat_badges.call().get_badge_info(id).unwrap();
+            assert_eq!(badge.num_code, 2);
+            assert_eq!(badge.num_issued, 0);
+
+            // Can issue badges to Django and Eve
+            stack.switch_account(accounts.alice).unwrap();
+            assert!(fat_badges.call_mut().issue(id, accounts.django).is_ok());
+            assert_eq!(
+                fat_badges.call_mut().issue(id, accounts.django),
+                Err(Error::Duplicated),
+                "Cannot issue duplicated badges"
+            );
+            stack.switch_account(accounts.bob).unwrap();
+            assert!(fat_badges.call_mut().issue(id, accounts.eve).is_ok());
+            assert_eq!(
+                fat_badges.call_mut().issue(id, accounts.frank),
+                Err(Error::RunOutOfCode),
+                "No code available to issue badges"
+            );
+
+            // Adding a new code solves the problem
+            stack.switch_account(accounts.alice).unwrap();
+            assert!(fat_badges
+                .call_mut()
+                .add_code(id, vec!["code3".to_string()])
+                .is_ok());
+            assert!(fat_badges.call_mut().issue(id, accounts.frank).is_ok());
+
+            // Code can be revealed, assigned in a random order
+            stack.switch_account(accounts.django).unwrap();
+            let django_code = fat_badges.call().get(id).unwrap();
+            stack.switch_account(accounts.eve).unwrap();
+            let eve_code = fat_badges.call().get(id).unwrap();
+            let mut codes = vec![django_code, eve_code];
+            codes.sort();
+            assert_eq!(codes, vec!["code1".to_string(), "code2".to_string()]);
+            stack.switch_account(accounts.frank).unwrap();
+            assert_eq!(fat_badges.call().get(id), Ok("code3".to_string()));
+            stack.switch_account(accounts.alice).unwrap();
+            assert_eq!(fat_badges.call().get(id), Err(Error::NotFound));
+
+            // Final checks
+            let badge = fat_badges.call().get_badge_info(id).unwrap();
+            assert_eq!(badge.num_code, 3);
+            assert_eq!(badge.num_issued, 3);
+            assert_eq!(fat_badges.call().get_total_badges(), 2);
+        }
+
+        #[ink::test]
+        fn emit_events() {
+            let accounts = default_accounts();
+
+            let stack = SharedCallStack::new(accounts.alice);
+            let fat_badges = Addressable::create_native(1, FatBadges::new(), stack.clone());
+
+            let id = fat_badges
+                .call_mut()
+                .new_badge("Phala Workshop: Easy".to_string())
+                .unwrap();
+            fat_badges
+                .call_mut()
+                .add_issuer(id, accounts.bob, None)
+                .unwrap();
+            fat_badges
+                .call_mut()
+                .remove_issuer(id, accounts.bob)
+                .unwrap();
+            fat_badges
+                .call_mut()
+                .add_code(id, vec!["code1".to_string(), "code2".to_string()])
+                .unwrap();
+            fat_badges.call_mut().issue(id, accounts.django).unwrap();
+            // Failed calls don't emit anything
+            assert_eq!(
+                fat_badges.call_mut().issue(id, accounts.django),
+                Err(Error::Duplicated)
+            );
+
+            let events = recorded_events();
+            assert_eq!(events.len(), 5);
+            assert!(matches!(
+                &events[0],
+                Event::BadgeCreated(e)
+                    if e.id == id && e.admin == accounts.alice && e.name == "Phala Workshop: Easy"
+            ));
+            assert!(matches!(
+                &events[1],
+                Event::IssuerAdded(e) if e.id == id && e.issuer == accounts.bob
+            ));
+            assert!(matches!(
+                &events[2],
+                Event::IssuerRemoved(e) if e.id == id && e.issuer == accounts.bob
+            ));
+            assert!(matches!(
+                &events[3],
+                Event::CodeAdded(e) if e.id == id && e.count == 2
+            ));
+            assert!(matches!(
+                &events[4],
+                Event::BadgeIssued(e) if e.id == id && e.dest == accounts.django
+            ));
+            // The signature topic plus two indexed fields
+            let raw: Vec<_> = ink_env::test::recorded_events().collect();
+            assert_eq!(raw[4].topics.len(), 3);
+        }
+
+        #[ink::test]
+        fn emit_admin_events() {
+            let accounts = default_accounts();
+
+            let stack = SharedCallStack::new(accounts.alice);
+            let old = Addressable::create_native(1, FatBadges::new(), stack.clone());
+            let new = Addressable::create_native(2, FatBadges::new(), stack.clone());
+            let id = old
+                .call_mut()
+                .new_badge("Phala Workshop: Easy".to_string())
+                .unwrap();
+            let badges = old.call().export_badges(0, 1).unwrap();
+            let num_events = recorded_events().len();
+
+            new.call_mut().import(badges).unwrap();
+            new.call_mut().finish_import().unwrap();
+            new.call_mut()
+                .set_code_mode(id, CodeMode::Committed)
+                .unwrap();
+            new.call_mut()
+                .set_issuance_rules(id, Some(10), Some(1), Some(2), Some(3))
+                .unwrap();
+            new.call_mut().add_issuer(id, accounts.bob, None).unwrap();
+            new.call_mut()
+                .migrate_accounts(id, vec![accounts.bob, accounts.charlie])
+                .unwrap();
+
+            let events = recorded_events();
+            let events = &events[num_events..];
+            assert_eq!(events.len(), 6);
+            assert!(matches!(&events[0], Event::SnapshotImported(e) if e.count == 1));
+            assert!(matches!(
+                &events[1],
+                Event::ImportFinished(e) if e.admin == accounts.alice
+            ));
+            assert!(matches!(
+                &events[2],
+                Event::CodeModeChanged(e) if e.id == id && e.mode == CodeMode::Committed
+            ));
+            assert!(matches!(
+                &events[3],
+                Event::IssuanceRulesUpdated(e)
+                    if e.id == id
+                        && e.max_supply == Some(10)
+                        && e.start_time == Some(1)
+                        && e.end_time == Some(2)
+                        && e.validity_period == Some(3)
+            ));
+            assert!(matches!(
+                &events[5],
+                Event::AccountsMigrated(e) if e.id == id && e.holders == 0 && e.issuers == 1
+            ));
+        }
+
+        #[ink::test]
+        fn revoke_badges() {
+            let accounts = default_accounts();
+
+            let stack = SharedCallStack::new(accounts.alice);
+            let fat_badges = Addressable::create_native(1, FatBadges::new(), stack.clone());
+            let id = fat_badges
+                .call_mut()
+                .new_badge("Phala Workshop: Easy".to_string())
+                .unwrap();
+            fat_badges
+                .call_mut()
+                .add_code(id, vec!["code1".to_string(), "code2".to_string()])
+                .unwrap();
+            fat_badges.call_mut().issue(id, accounts.django).unwrap();
+            fat_badges.call_mut().issue(id, accounts.eve).unwrap();
+            stack.switch_account(accounts.django).unwrap();
+            let django_code = fat_badges.call().get(id).unwrap();
+
+            // Only the badge admin can revoke an issued badge
+            stack.switch_account(accounts.bob).unwrap();
+            assert_eq!(
+                fat_badges
+                    .call_mut()
+                    .revoke(id, accounts.django, "mistake".to_string(), true),
+                Err(Error::BadOrigin)
+            );
+            stack.switch_account(accounts.alice).unwrap();
+            assert_eq!(
+                fat_badges
+                    .call_mut()
+                    .revoke(id, accounts.frank, "mistake".to_string(), true),
+                Err(Error::NotFound)
+            );
+
+            // Revoke Django's badge and return the code
+            assert!(fat_badges
+                .call_mut()
+                .revoke(id, accounts.django, "mistake".to_string(), true)
+                .is_ok());
+            assert_eq!(
+                fat_badges.call().get_revocation(id, accounts.django),
+                Some("mistake".to_string())
+            );
+            let badge = fat_badges.call().get_badge_info(id).unwrap();
+            assert_eq!(badge.num_issued, 1);
+            assert_eq!(badge.num_available(), 1);
+            stack.switch_account(accounts.django).unwrap();
+            assert_eq!(fat_badges.call().get(id), Err(Error::Revoked));
+
+            // The returned code goes to Frank
+            stack.switch_account(accounts.alice).unwrap();
+            assert!(fat_badges.call_mut().issue(id, accounts.frank).is_ok());
+            stack.switch_account(accounts.frank).unwrap();
+            assert_eq!(fat_badges.call().get(id), Ok(django_code));
+
+            // Revoke Eve's badge and burn the code
+            stack.switch_account(accounts.alice).unwrap();
+            assert!(fat_badges
+                .call_mut()
+                .revoke(id, accounts.eve, "fraud".to_string(), false)
+                .is_ok());
+            assert_eq!(
+                fat_badges.call_mut().issue(id, accounts.charlie),
+                Err(Error::RunOutOfCode)
+            );
+
+            // Django can be issued again with new code
+            fat_badges
+                .call_mut()
+                .add_code(id, vec!["code3".to_string()])
+                .unwrap();
+            assert!(fat_badges.call_mut().issue(id, accounts.django).is_ok());
+            assert_eq!(fat_badges.call().get_revocation(id, accounts.django), None);
+            stack.switch_account(accounts.django).unwrap();
+            assert_eq!(fat_badges.call().get(id), Ok("code3".to_string()));
+
+            let badge = fat_badges.call().get_badge_info(id).unwrap();
+            assert_eq!(badge.num_code, 3);
+            assert_eq!(badge.num_issued, 2);
+            assert_eq!(badge.num_available(), 0);
+        }
+
+        #[ink::test]
+        fn transfer_admins() {
+            let accounts = default_accounts();
+
+            let stack = SharedCallStack::new(accounts.alice);
+            let fat_badges = Addressable::create_native(1, FatBadges::new(), stack.clone());
+            let id = fat_badges
+                .call_mut()
+                .new_badge("Phala Workshop: Easy".to_string())
+                .unwrap();
+
+            // Alice hands the badge over to Bob
+            assert!(fat_badges
+                .call_mut()
+                .transfer_badge_admin(id, accounts.bob)
+                .is_ok());
+            assert_eq!(
+                fat_badges.call().get_pending_badge_admin(id),
+                Some(accounts.bob)
+            );
+            // Alice is still the admin until Bob accepts it
+            assert!(fat_badges
+                .call_mut()
+                .add_issuer(id, accounts.eve, None)
+                .is_ok());
+            stack.switch_account(accounts.charlie).unwrap();
+            assert_eq!(
+                fat_badges.call_mut().accept_badge_admin(id),
+                Err(Error::BadOrigin),
+                "Only the pending admin can accept"
+            );
+            stack.switch_account(accounts.bob).unwrap();
+            assert!(fat_badges.call_mut().accept_badge_admin(id).is_ok());
+            assert_eq!(fat_badges.call().get_pending_badge_admin(id), None);
+            assert_eq!(
+                fat_badges.call().get_badge_info(id).unwrap().admin,
+                accounts.bob
+            );
+            // Bob can manage the badge now, and Alice only as the contract owner
+            assert!(fat_badges
+                .call_mut()
+                .add_code(id, vec!["code1".to_string()])
+                .is_ok());
+            stack.switch_account(accounts.alice).unwrap();
+            assert!(fat_badges
+                .call_mut()
+                .add_issuer(id, accounts.frank, None)
+                .is_ok());
+
+            // Bob renounces the badge
+            stack.switch_account(accounts.bob).unwrap();
+            assert!(fat_badges.call_mut().renounce_badge_admin(id).is_ok());
+            assert_eq!(
+                fat_badges.call_mut().add_issuer(id, accounts.frank, None),
+                Err(Error::BadOrigin)
+            );
+            // The existing issuer still works
+            stack.switch_account(accounts.eve).unwrap();
+            assert!(fat_badges.call_mut().issue(id, accounts.django).is_ok());
+
+            // Alice hands the contract over to Bob
+            stack.switch_account(accounts.alice).unwrap();
+            assert!(fat_badges
+                .call_mut()
+                .transfer_ownership(accounts.bob)
+                .is_ok());
+            assert_eq!(fat_badges.call().get_pending_admin(), Some(accounts.bob));
+            stack.switch_account(accounts.charlie).unwrap();
+            assert_eq!(
+                fat_badges.call_mut().accept_ownership(),
+                Err(Error::BadOrigin)
+            );
+            stack.switch_account(accounts.bob).unwrap();
+            assert!(fat_badges.call_mut().accept_ownership().is_ok());
+            assert_eq!(fat_badges.call().get_admin(), accounts.bob);
+            assert_eq!(fat_badges.call().get_pending_admin(), None);
+            stack.switch_account(accounts.alice).unwrap();
+            assert_eq!(
+                fat_badges.call_mut().transfer_ownership(accounts.alice),
+                Err(Error::BadOrigin)
+            );
+            assert_eq!(
+                fat_badges.call_mut().new_badge("Not owned".to_string()),
+                Err(Error::BadOrigin),
+                "The previous owner has no role left"
+            );
+
+            // Bob renounces the contract
+            stack.switch_account(accounts.bob).unwrap();
+            assert!(fat_badges.call_mut().renounce_ownership().is_ok());
+            assert_eq!(fat_badges.call().get_admin(), zero_account());
+            assert_eq!(
+                fat_badges.call_mut().renounce_ownership(),
+                Err(Error::BadOrigin)
+            );
+        }
+
+        #[ink::test]
+        fn list_badges_issuers_and_holders() {
+            let accounts = default_accounts();
+
+            let stack = SharedCallStack::new(accounts.alice);
+            let fat_badges = Addressable::create_native(1, FatBadges::new(), stack.clone());
+            for name in ["badge0", "badge1", "badge2"] {
+                fat_badges.call_mut().new_badge(name.to_string()).unwrap();
+            }
+
+            // Badges
+            let names: Vec<_> = fat_badges
+                .call()
+                .list_badges(1, 5)
+                .into_iter()
+                .map(|badge| badge.name)
+                .collect();
+            assert_eq!(names, vec!["badge1".to_string(), "badge2".to_string()]);
+            assert_eq!(fat_badges.call().list_badges(3, 5), vec![]);
+
+            // Issuers
+            for issuer in [accounts.bob, accounts.charlie, accounts.eve] {
+                fat_badges.call_mut().add_issuer(0, issuer, None).unwrap();
+            }
+            fat_badges
+                .call_mut()
+                .add_issuer(0, accounts.eve, None)
+                .unwrap();
+            assert_eq!(
+                fat_badges.call().list_issuers(0, 0, 10),
+                vec![accounts.bob, accounts.charlie, accounts.eve]
+            );
+            fat_badges
+                .call_mut()
+                .remove_issuer(0, accounts.bob)
+                .unwrap();
+            assert_eq!(
+                fat_badges.call().list_issuers(0, 0, 10),
+                vec![accounts.eve, accounts.charlie]
+            );
+            assert_eq!(
+                fat_badges.call().list_issuers(0, 1, 1),
+                vec![accounts.charlie]
+            );
+            assert_eq!(fat_badges.call().list_issuers(1, 0, 10), vec![]);
+
+            // Holders
+            let codes = vec!["code1".to_string(), "code2".to_string()];
+            fat_badges.call_mut().add_code(0, codes.clone()).unwrap();
+            fat_badges.call_mut().add_code(1, codes).unwrap();
+            fat_badges.call_mut().issue(0, accounts.django).unwrap();
+            fat_badges.call_mut().issue(0, accounts.frank).unwrap();
+            fat_badges.call_mut().issue(1, accounts.django).unwrap();
+            assert_eq!(
+                fat_badges.call().list_holders(0, 0, 10),
+                vec![accounts.django, accounts.frank]
+            );
+            assert_eq!(
+                fat_badges.call().badges_of(accounts.django, 0, 10),
+                vec![0, 1]
+            );
+
+            fat_badges
+                .call_mut()
+                .revoke(0, accounts.django, "test".to_string(), false)
+                .unwrap();
+            assert_eq!(
+                fat_badges.call().list_holders(0, 0, 10),
+                vec![accounts.frank]
+            );
+            assert_eq!(fat_badges.call().badges_of(accounts.django, 0, 10), vec![1]);
+            assert_eq!(fat_badges.call().badges_of(accounts.frank, 0, 10), vec![0]);
+            assert_eq!(fat_badges.call().badges_of(accounts.eve, 0, 10), vec![]);
+        }
+
+        #[ink::test]
+        fn commit_reveal_code() {
+            let accounts = default_accounts();
+
+            let stack = SharedCallStack::new(accounts.alice);
+            let fat_badges = Addressable::create_native(1, FatBadges::new(), stack.clone());
+            let id = fat_badges
+                .call_mut()
+                .new_badge("Phala Workshop: Easy".to_string())
+                .unwrap();
+
+            // Switch to the commit-reveal mode
+            assert_eq!(
+                fat_badges.call_mut().add_code_hashes(id, vec![[0u8; 32]]),
+                Err(Error::WrongCodeMode)
+            );
+            assert!(fat_badges
+                .call_mut()
+                .set_code_mode(id, CodeMode::Committed)
+                .is_ok());
+            assert_eq!(
+                fat_badges
+                    .call_mut()
+                    .add_code(id, vec!["code1".to_string()]),
+                Err(Error::WrongCodeMode)
+            );
+            assert!(fat_badges
+                .call_mut()
+                .add_code_hashes(id, vec![hash_code("code1"), hash_code("leaked")])
+                .is_ok());
+            // The code can only be replaced by its hash
+            assert_eq!(
+                fat_badges
+                    .call_mut()
+                    .replace_code(id, 1, "code2".to_string()),
+                Err(Error::WrongCodeMode)
+            );
+            assert!(fat_badges
+                .call_mut()
+                .replace_code_hash(id, 1, hash_code("code2"))
+                .is_ok());
+            assert_eq!(
+                fat_badges.call_mut().set_code_mode(id, CodeMode::Plaintext),
+                Err(Error::CodeModeLocked),
+                "Cannot change mode after adding code"
+            );
+
+            // Issue to Django and Eve
+            fat_badges.call_mut().issue(id, accounts.django).unwrap();
+            fat_badges.call_mut().issue(id, accounts.eve).unwrap();
+            let codes = ["code1", "code2"];
+            let django_slot = fat_badges
+                .call()
+                .get_code_slot(id, accounts.django)
+                .unwrap();
+            let eve_slot = fat_badges.call().get_code_slot(id, accounts.eve).unwrap();
+            assert_eq!(django_slot + eve_slot, 1);
+            assert_eq!(
+                fat_badges
+                    .call_mut()
+                    .deliver_code(id, accounts.frank, b"sealed".to_vec()),
+                Err(Error::NotFound)
+            );
+            assert!(fat_badges
+                .call_mut()
+                .deliver_code(id, accounts.eve, b"sealed-code2".to_vec())
+                .is_ok());
+
+            // The plaintext code is not available
+            stack.switch_account(accounts.eve).unwrap();
+            assert_eq!(fat_badges.call().get(id), Err(Error::WrongCodeMode));
+            assert_eq!(
+                fat_badges.call().get_sealed_code(id),
+                Ok(b"sealed-code2".to_vec())
+            );
+            stack.switch_account(accounts.django).unwrap();
+            assert_eq!(
+                fat_badges.call().get_sealed_code(id),
+                Err(Error::NotDelivered)
+            );
+
+            // The decrypted code can be checked against the commitment
+            assert_eq!(
+                fat_badges.call().verify_code(
+                    id,
+                    accounts.eve,
+                    codes[eve_slot as usize].to_string()
+                ),
+                Ok(true)
+            );
+            assert_eq!(
+                fat_badges.call().verify_code(
+                    id,
+                    accounts.eve,
+                    codes[django_slot as usize].to_string()
+                ),
+                Ok(false)
+            );
+            assert_eq!(
+                fat_badges
+                    .call()
+                    .verify_code(id, accounts.frank, "code1".to_string()),
+                Err(Error::NotFound)
+            );
+        }
+
+        #[ink::test]
+        fn seal_code_at_upload() {
+            let accounts = default_accounts();
+
+            let stack = SharedCallStack::new(accounts.alice);
+            let fat_badges = Addressable::create_native(1, FatBadges::new(), stack.clone());
+            let id = fat_badges
+                .call_mut()
+                .new_badge("Phala Workshop: Easy".to_string())
+                .unwrap();
+            let sealed = |code: &str| (hash_code(code), format!("sealed-{}", code).into_bytes());
+            assert_eq!(
+                fat_badges
+                    .call_mut()
+                    .add_sealed_code(id, vec![sealed("code0")]),
+                Err(Error::WrongCodeMode)
+            );
+            fat_badges
+                .call_mut()
+                .set_code_mode(id, CodeMode::Committed)
+                .unwrap();
+            assert!(fat_badges
+                .call_mut()
+                .add_sealed_code(id, vec![sealed("code0"), sealed("code1")])
+                .is_ok());
+            fat_badges
+                .call_mut()
+                .add_code_hashes(id, vec![hash_code("code2")])
+                .unwrap();
+            assert_eq!(fat_badges.call().get_badge_info(id).unwrap().num_code, 3);
+            // The replaced code has to be delivered
+            fat_badges
+                .call_mut()
+                .replace_code_hash(id, 1, hash_code("code1-new"))
+                .unwrap();
+
+            // Each holder reads the code sealed for the assigned slot without a delivery
+            let holders = [accounts.django, accounts.eve, accounts.frank];
+            for holder in holders {
+                fat_badges.call_mut().issue(id, holder).unwrap();
+            }
+            for holder in holders {
+                let slot = fat_badges.call().get_code_slot(id, holder).unwrap();
+                stack.switch_account(holder).unwrap();
+                let sealed_code = fat_badges.call().get_sealed_code(id);
+                if slot == 0 {
+                    assert_eq!(sealed_code, Ok(b"sealed-code0".to_vec()));
+                } else {
+                    assert_eq!(sealed_code, Err(Error::NotDelivered));
+                }
+                stack.switch_account(accounts.alice).unwrap();
+                if slot != 0 {
+                    fat_badges
+                        .call_mut()
+                        .deliver_code(id, holder, b"delivered".to_vec())
+                        .unwrap();
+                }
+                stack.switch_account(holder).unwrap();
+                assert!(fat_badges.call().get_sealed_code(id).is_ok());
+                stack.switch_account(accounts.alice).unwrap();
+            }
+
+            // The sealed code is exported with its slot
+            let page = fat_badges.call().export_code(id, 0, 3).unwrap();
+            let entries: Vec<SnapshotEntry> = Decode::decode(&mut &page[..]).unwrap();
+            assert_eq!(entries.len(), 4);
+            assert!(matches!(
+                &entries[1],
+                SnapshotEntry::SealedCode { id: 0, slot: 0, sealed_code }
+                    if sealed_code == b"sealed-code0"
+            ));
+        }
+
+        #[ink::test]
+        fn issuance_rules() {
+            let accounts = default_accounts();
+
+            let stack = SharedCallStack::new(accounts.alice);
+            let fat_badges = Addressable::create_native(1, FatBadges::new(), stack.clone());
+            let id = fat_badges
+                .call_mut()
+                .new_badge("Phala Workshop: Easy".to_string())
+                .unwrap();
+            let codes = (0..5).map(|i| format!("code{}", i)).collect();
+            fat_badges.call_mut().add_code(id, codes).unwrap();
+
+            // Not started yet
+            let t0 = ink_env::block_timestamp::<Environment>();
+            stack.switch_account(accounts.bob).unwrap();
+            assert_eq!(
+                fat_badges
+                    .call_mut()
+                    .set_issuance_rules(id, None, None, None, None),
+                Err(Error::BadOrigin)
+            );
+            stack.switch_account(accounts.alice).unwrap();
+            assert!(fat_badges
+                .call_mut()
+                .set_issuance_rules(id, Some(1), Some(t0 + 1), None, None)
+                .is_ok());
+            assert_eq!(
+                fat_badges.call_mut().issue(id, accounts.django),
+                Err(Error::NotStarted)
+            );
+
+            // Started in the next block, but capped at one holder
+            ink_env::test::advance_block::<Environment>();
+            let t1 = ink_env::block_timestamp::<Environment>();
+            assert!(fat_badges.call_mut().issue(id, accounts.django).is_ok());
+            assert_eq!(
+                fat_badges.call().get_issued_at(id, accounts.django),
+                Some(t1)
+            );
+            assert_eq!(
+                fat_badges.call_mut().issue(id, accounts.eve),
+                Err(Error::SupplyCapReached)
+            );
+
+            // An empty window is rejected
+            assert_eq!(
+                fat_badges
+                    .call_mut()
+                    .set_issuance_rules(id, None, Some(t1), Some(t1), None),
+                Err(Error::InvalidIssuanceWindow)
+            );
+            assert_eq!(
+                fat_badges
+                    .call_mut()
+                    .set_issuance_rules(id, None, Some(t1 + 1), Some(t1), None),
+                Err(Error::InvalidIssuanceWindow)
+            );
+
+            // Ends in the next block
+            assert!(fat_badges
+                .call_mut()
+                .set_issuance_rules(id, None, Some(t0 + 1), Some(t1 + 1), None)
+                .is_ok());
+            assert!(fat_badges.call_mut().issue(id, accounts.eve).is_ok());
+            stack.switch_account(accounts.django).unwrap();
+            assert!(fat_badges.call().get(id).is_ok());
+
+            ink_env::test::advance_block::<Environment>();
+            stack.switch_account(accounts.alice).unwrap();
+            assert_eq!(
+                fat_badges.call_mut().issue(id, accounts.frank),
+                Err(Error::Ended)
+            );
+
+            // The badges issued at t1 expire after the validity period
+            let t2 = ink_env::block_timestamp::<Environment>();
+            assert!(fat_badges
+                .call_mut()
+                .set_issuance_rules(id, None, None, None, Some(t2 - t1))
+                .is_ok());
+            stack.switch_account(accounts.django).unwrap();
+            assert_eq!(fat_badges.call().get(id), Err(Error::Expired));
+            stack.switch_account(accounts.eve).unwrap();
+            assert_eq!(fat_badges.call().get(id), Err(Error::Expired));
+            // The period applies retroactively, so a longer one makes them valid again
+            stack.switch_account(accounts.alice).unwrap();
+            assert!(fat_badges
+                .call_mut()
+                .set_issuance_rules(id, None, None, None, Some(t2 - t1 + 1))
+                .is_ok());
+            stack.switch_account(accounts.eve).unwrap();
+            assert!(fat_badges.call().get(id).is_ok());
+        }
+
+        #[ink::test]
+        fn psp34_view() {
+            let accounts = default_accounts();
+
+            let stack = SharedCallStack::new(accounts.alice);
+            let fat_badges = Addressable::create_native(1, FatBadges::new(), stack.clone());
+            let codes = vec!["code1".to_string(), "code2".to_string()];
+            for name in ["badge0", "badge1"] {
+                let id = fat_badges.call_mut().new_badge(name.to_string()).unwrap();
+                fat_badges.call_mut().add_code(id, codes.clone()).unwrap();
+            }
+            fat_badges.call_mut().issue(0, accounts.django).unwrap();
+            fat_badges.call_mut().issue(1, accounts.django).unwrap();
+            fat_badges.call_mut().issue(1, accounts.eve).unwrap();
+
+            assert_eq!(fat_badges.call().total_supply(), 3);
+            assert_eq!(fat_badges.call().balance_of(accounts.django), 2);
+            assert_eq!(fat_badges.call().balance_of(accounts.eve), 1);
+            assert_eq!(fat_badges.call().balance_of(accounts.frank), 0);
+            assert_eq!(
+                fat_badges.call().owner_of(token_id(1, accounts.eve)),
+                Some(accounts.eve)
+            );
+            assert_eq!(fat_badges.call().owner_of(token_id(0, accounts.eve)), None);
+            assert_eq!(fat_badges.call().owner_of(Id::U32(0)), None);
+            assert_eq!(
+                fat_badges
+                    .call()
+                    .get_attribute(token_id(1, accounts.eve), b"name".to_vec()),
+                Some(b"badge1".to_vec())
+            );
+            assert_eq!(
+                fat_badges
+                    .call()
+                    .get_attribute(token_id(0, accounts.eve), b"name".to_vec()),
+                None
+            );
+
+            // Soulbound
+            stack.switch_account(accounts.eve).unwrap();
+            assert!(fat_badges
+                .call_mut()
+                .transfer(accounts.frank, token_id(1, accounts.eve), vec![])
+                .is_err());
+            assert!(fat_badges
+                .call_mut()
+                .approve(accounts.frank, None, true)
+                .is_err());
+            assert_eq!(
+                fat_badges.call().owner_of(token_id(1, accounts.eve)),
+                Some(accounts.eve)
+            );
+
+            // Revoked badges are burned
+            stack.switch_account(accounts.alice).unwrap();
+            fat_badges
+                .call_mut()
+                .revoke(1, accounts.eve, "test".to_string(), false)
+                .unwrap();
+            assert_eq!(fat_badges.call().owner_of(token_id(1, accounts.eve)), None);
+            assert_eq!(fat_badges.call().total_supply(), 2);
+        }
+
+        #[ink::test]
+        fn badge_metadata() {
+            let accounts = default_accounts();
+
+            let stack = SharedCallStack::new(accounts.alice);
+            let fat_badges = Addressable::create_native(1, FatBadges::new(), stack.clone());
+            let id = fat_badges
+                .call_mut()
+                .new_badge("Phala Workshop: Easy".to_string())
+                .unwrap();
+            assert_eq!(
+                fat_badges.call().get_badge_info(id).unwrap().metadata,
+                BadgeMetadata::default()
+            );
+
+            let metadata = BadgeMetadata {
+                description: "Solved the beginner challenge".to_string(),
+                image_uri: "https://i.imgur.com/mVNh6Nh.png".to_string(),
+                external_link: "https://github.com/Phala-Network/oracle-workshop".to_string(),
+                attributes: vec![("level".to_string(), "easy".to_string())],
+            };
+            stack.switch_account(accounts.bob).unwrap();
+            assert_eq!(
+                fat_badges
+                    .call_mut()
+                    .update_badge_metadata(id, metadata.clone()),
+                Err(Error::BadOrigin)
+            );
+            stack.switch_account(accounts.alice).unwrap();
+            let too_many = BadgeMetadata {
+                attributes: vec![("k".to_string(), "v".to_string()); MAX_ATTRIBUTES + 1],
+                ..metadata.clone()
+            };
+            assert_eq!(
+                fat_badges.call_mut().update_badge_metadata(id, too_many),
+                Err(Error::InvalidMetadata)
+            );
+            assert!(fat_badges
+                .call_mut()
+                .update_badge_metadata(id, metadata.clone())
+                .is_ok());
+            assert_eq!(
+                fat_badges.call().get_badge_info(id).unwrap().metadata,
+                metadata
+            );
+
+            // Exposed as PSP34 attributes
+            fat_badges
+                .call_mut()
+                .add_code(id, vec!["code1".to_string()])
+                .unwrap();
+            fat_badges.call_mut().issue(id, accounts.django).unwrap();
+            let token = token_id(id, accounts.django);
+            assert_eq!(
+                fat_badges
+                    .call()
+                    .get_attribute(token.clone(), b"image".to_vec()),
+                Some(b"https://i.imgur.com/mVNh6Nh.png".to_vec())
+            );
+            assert_eq!(
+                fat_badges
+                    .call()
+                    .get_attribute(token.clone(), b"level".to_vec()),
+                Some(b"easy".to_vec())
+            );
+            assert_eq!(
+                fat_badges.call().get_attribute(token, b"color".to_vec()),
+                None
+            );
+        }
+
+        #[ink::test]
+        fn decode_legacy_badge_info() {
+            // The layout before any field was appended
+            #[derive(Encode)]
+            struct LegacyBadgeInfo {
+                id: u32,
+                admin: AccountId,
+                name: String,
+                num_code: u32,
+                num_issued: u32,
+            }
+            let accounts = default_accounts();
+            let legacy = LegacyBadgeInfo {
+                id: 1,
+                admin: accounts.alice,
+                name: "Phala Workshop: Easy".to_string(),
+                num_code: 3,
+                num_issued: 2,
+            };
+            let badge = BadgeInfo::decode(&mut &legacy.encode()[..]).unwrap();
+            assert_eq!(
+                badge,
+                BadgeInfo {
+                    id: 1,
+                    admin: accounts.alice,
+                    name: "Phala Workshop: Easy".to_string(),
+                    num_code: 3,
+                    num_issued: 2,
+                    next_code: 2,
+                    num_returned: 0,
+                    code_mode: CodeMode::Plaintext,
+                    max_supply: None,
+                    start_time: None,
+                    end_time: None,
+                    validity_period: None,
+                    metadata: BadgeMetadata::default(),
+                    num_invalid: 0,
+                    prerequisites: vec![],
+                }
+            );
+            assert_eq!(badge.num_available(), 1);
+
+            // The current layout round-trips
+            let encoded = badge.encode();
+            assert_eq!(BadgeInfo::decode(&mut &encoded[..]).unwrap(), badge);
+            // Truncated in the middle of a field
+            assert!(BadgeInfo::decode(&mut &encoded[..encoded.len() - 1]).is_err());
+        }
+
+        #[ink::test]
+        fn issue_in_batch() {
+            let accounts = default_accounts();
+
+            let stack = SharedCallStack::new(accounts.alice);
+            let fat_badges = Addressable::create_native(1, FatBadges::new(), stack.clone());
+            let id = fat_badges
+                .call_mut()
+                .new_badge("Phala Workshop: Easy".to_string())
+                .unwrap();
+            fat_badges
+                .call_mut()
+                .add_code(id, vec!["code1".to_string(), "code2".to_string()])
+                .unwrap();
+            fat_badges
+                .call_mut()
+                .add_issuer(id, accounts.bob, None)
+                .unwrap();
+
+            stack.switch_account(accounts.charlie).unwrap();
+            assert_eq!(
+                fat_badges.call_mut().issue_batch(id, vec![accounts.django]),
+                Err(Error::NotAnIssuer)
+            );
+            stack.switch_account(accounts.bob).unwrap();
+            assert_eq!(
+                fat_badges.call_mut().issue_batch(
+                    id,
+                    vec![
+                        accounts.django,
+                        accounts.eve,
+                        accounts.django,
+                        accounts.frank
+                    ]
+                ),
+                Ok(vec![
+                    Ok(()),
+                    Ok(()),
+                    Err(Error::Duplicated),
+                    Err(Error::RunOutOfCode)
+                ])
+            );
+            let badge = fat_badges.call().get_badge_info(id).unwrap();
+            assert_eq!(badge.num_issued, 2);
+            assert_eq!(
+                fat_badges.call().list_holders(id, 0, 10),
+                vec![accounts.django, accounts.eve]
+            );
+            stack.switch_account(accounts.eve).unwrap();
+            assert!(fat_badges.call().get(id).is_ok());
+        }
+
+        #[ink::test]
+        fn issuer_quota() {
+            let accounts = default_accounts();
+
+            let stack = SharedCallStack::new(accounts.alice);
+            let fat_badges = Addressable::create_native(1, FatBadges::new(), stack.clone());
+            let id = fat_badges
+                .call_mut()
+                .new_badge("Phala Workshop: Easy".to_string())
+                .unwrap();
+            let codes = (0..5).map(|i| format!("code{}", i)).collect();
+            fat_badges.call_mut().add_code(id, codes).unwrap();
+            fat_badges
+                .call_mut()
+                .add_issuer(id, accounts.bob, Some(2))
+                .unwrap();
+            fat_badges
+                .call_mut()
+                .add_issuer(id, accounts.charlie, None)
+                .unwrap();
+            assert_eq!(
+                fat_badges.call().issuer_allowance(id, accounts.bob),
+                Ok(Some(2))
+            );
+            assert_eq!(
+                fat_badges.call().issuer_allowance(id, accounts.charlie),
+                Ok(None)
+            );
+            assert_eq!(
+                fat_badges.call().issuer_allowance(id, accounts.eve),
+                Err(Error::NotAnIssuer)
+            );
+
+            // Bob can issue two badges only
+            stack.switch_account(accounts.bob).unwrap();
+            assert!(fat_badges.call_mut().issue(id, accounts.django).is_ok());
+            assert_eq!(
+                fat_badges.call_mut().issue(id, accounts.django),
+                Err(Error::Duplicated),
+                "Failed issuance doesn't consume the allowance"
+            );
+            assert_eq!(
+                fat_badges.call().issuer_allowance(id, accounts.bob),
+                Ok(Some(1))
+            );
+            assert_eq!(
+                fat_badges
+                    .call_mut()
+                    .issue_batch(id, vec![accounts.eve, accounts.frank]),
+                Ok(vec![Ok(()), Err(Error::QuotaExceeded)])
+            );
+            assert_eq!(
+                fat_badges.call().issuer_allowance(id, accounts.bob),
+                Ok(Some(0))
+            );
+
+            // Charlie and the admin are not limited
+            stack.switch_account(accounts.charlie).unwrap();
+            assert!(fat_badges.call_mut().issue(id, accounts.frank).is_ok());
+            stack.switch_account(accounts.alice).unwrap();
+            assert!(fat_badges.call_mut().issue(id, accounts.charlie).is_ok());
+
+            // The admin can top up the allowance
+            fat_badges
+                .call_mut()
+                .add_issuer(id, accounts.bob, Some(1))
+                .unwrap();
+            stack.switch_account(accounts.bob).unwrap();
+            assert!(fat_badges.call_mut().issue(id, accounts.bob).is_ok());
+        }
+
+        #[ink::test]
+        fn manage_code_inventory() {
+            let accounts = default_accounts();
+
+            let stack = SharedCallStack::new(accounts.alice);
+            let fat_badges = Addressable::create_native(1, FatBadges::new(), stack.clone());
+            let id = fat_badges
+                .call_mut()
+                .new_badge("Phala Workshop: Easy".to_string())
+                .unwrap();
+            fat_badges
+                .call_mut()
+                .add_code(id, vec!["code0".to_string()])
+                .unwrap();
+            fat_badges.call_mut().issue(id, accounts.django).unwrap();
+            let codes = (1..5).map(|i| format!("code{}", i)).collect();
+            fat_badges.call_mut().add_code(id, codes).unwrap();
+
+            // Slot 0 is issued, and slot 5 doesn't exist
+            assert_eq!(
+                fat_badges.call_mut().invalidate_code(id, 0, 2),
+                Err(Error::InvalidCodeSlot)
+            );
+            assert_eq!(
+                fat_badges.call_mut().invalidate_code(id, 4, 6),
+                Err(Error::InvalidCodeSlot)
+            );
+            assert_eq!(
+                fat_badges
+                    .call_mut()
+                    .replace_code(id, 0, "leaked".to_string()),
+                Err(Error::InvalidCodeSlot)
+            );
+            stack.switch_account(accounts.bob).unwrap();
+            assert_eq!(
+                fat_badges.call_mut().invalidate_code(id, 1, 3),
+                Err(Error::BadOrigin)
+            );
+            assert_eq!(
+                fat_badges.call().get_code_inventory(id),
+                Err(Error::BadOrigin)
+            );
+
+            // Invalidate slot 1 and 2, and replace slot 3
+            stack.switch_account(accounts.alice).unwrap();
+            assert_eq!(
+                fat_badges.call_mut().replace_code_hash(id, 3, [0u8; 32]),
+                Err(Error::WrongCodeMode)
+            );
+            assert!(fat_badges.call_mut().invalidate_code(id, 1, 3).is_ok());
+            assert!(fat_badges
+                .call_mut()
+                .replace_code(id, 3, "fixed3".to_string())
+                .is_ok());
+            assert_eq!(
+                fat_badges.call().get_code_inventory(id),
+                Ok(CodeInventory {
+                    total: 5,
+                    issued: 1,
+                    available: 2,
+                    returned: 0,
+                    invalidated: 2,
+                })
+            );
+
+            // An invalidated slot can be recovered by replacing it
+            assert!(fat_badges
+                .call_mut()
+                .replace_code(id, 2, "fixed2".to_string())
+                .is_ok());
+            assert_eq!(
+                fat_badges.call().get_code_inventory(id).unwrap().available,
+                3
+            );
+
+            // The invalidated code is skipped
+            let holders = [accounts.eve, accounts.frank, accounts.charlie];
+            for holder in holders {
+                fat_badges.call_mut().issue(id, holder).unwrap();
+            }
+            assert_eq!(
+                fat_badges.call_mut().issue(id, accounts.bob),
+                Err(Error::RunOutOfCode)
+            );
+            let mut issued: Vec<_> = holders
+                .iter()
+                .map(|holder| {
+                    stack.switch_account(*holder).unwrap();
+                    fat_badges.call().get(id).unwrap()
+                })
+                .collect();
+            issued.sort();
+            assert_eq!(issued, vec!["code4", "fixed2", "fixed3"]);
+
+            // Issued code cannot be replaced
+            stack.switch_account(accounts.alice).unwrap();
+            assert_eq!(
+                fat_badges
+                    .call_mut()
+                    .replace_code(id, 2, "leaked".to_string()),
+                Err(Error::InvalidCodeSlot)
+            );
+        }
+
+        #[ink::test]
+        fn shuffle_code_slots() {
+            let accounts = default_accounts();
+            let holder = |i: u8| AccountId::from([100 + i; 32]);
+
+            let stack = SharedCallStack::new(accounts.alice);
+            let fat_badges = Addressable::create_native(1, FatBadges::new(), stack.clone());
+            let id = fat_badges
+                .call_mut()
+                .new_badge("Phala Workshop: Easy".to_string())
+                .unwrap();
+            let codes = (0..12).map(|i| format!("code{}", i)).collect();
+            fat_badges.call_mut().add_code(id, codes).unwrap();
+
+            // Issue 4 badges, and return the code of 2 of them
+            let mut issued = Vec::new();
+            for i in 0..4 {
+                fat_badges.call_mut().issue(id, holder(i)).unwrap();
+                let slot = fat_badges.call().get_code_slot(id, holder(i)).unwrap();
+                issued.push(slot);
+            }
+            assert_ne!(issued, vec![0, 1, 2, 3], "Not issued in the upload order");
+            for i in 0..2 {
+                fat_badges
+                    .call_mut()
+                    .revoke(id, holder(i), "returned".to_string(), true)
+                    .unwrap();
+            }
+            // Invalidate 2 slots never drawn
+            let invalid: Vec<u32> = (0..12)
+                .filter(|slot| !issued.contains(slot))
+                .take(2)
+                .collect();
+            for slot in invalid.iter() {
+                fat_badges
+                    .call_mut()
+                    .invalidate_code(id, *slot, *slot + 1)
+                    .unwrap();
+            }
+            assert_eq!(
+                fat_badges.call().get_code_inventory(id),
+                Ok(CodeInventory {
+                    total: 12,
+                    issued: 2,
+                    available: 8,
+                    returned: 2,
+                    invalidated: 2,
+                })
+            );
+
+            // Drain all the code
+            for i in 4..12 {
+                fat_badges.call_mut().issue(id, holder(i)).unwrap();
+            }
+            assert_eq!(
+                fat_badges.call_mut().issue(id, holder(12)),
+                Err(Error::RunOutOfCode)
+            );
+            assert_eq!(
+                fat_badges.call().get_code_inventory(id).unwrap().available,
+                0
+            );
+
+            // Each valid slot is issued exactly once, including the returned ones
+            let mut held: Vec<u32> = (2..12)
+                .map(|i| fat_badges.call().get_code_slot(id, holder(i)).unwrap())
+                .collect();
+            held.sort();
+            let valid: Vec<u32> = (0..12).filter(|slot| !invalid.contains(slot)).collect();
+            assert_eq!(held, valid);
+            assert!(held.contains(&issued[0]) && held.contains(&issued[1]));
+        }
+
+        #[ink::test]
+        fn draw_without_modulo_bias() {
+            let fat_badges = FatBadges::new();
+            let mut badge = BadgeInfo {
+                id: 0,
+                admin: default_accounts().alice,
+                name: "badge".to_string(),
+                num_code: 0,
+                num_issued: 0,
+                next_code: 0,
+                num_returned: 0,
+                code_mode: CodeMode::Plaintext,
+                max_supply: None,
+                start_time: None,
+                end_time: None,
+                validity_period: None,
+                metadata: BadgeMetadata::default(),
+                num_invalid: 0,
+                prerequisites: Vec::new(),
+            };
+            // Almost half of the random numbers are rejected when `n` is just above `2^31`
+            for n in [1, 2, 3, 7, u32::MAX / 2 + 2, u32::MAX] {
+                for i in 0..8 {
+                    badge.num_issued = i;
+                    assert!(fat_badges.random_below(&badge, n) < n);
+                }
+            }
+        }
+
+        #[ink::test]
+        fn holder_queries() {
+            let accounts = default_accounts();
+
+            let stack = SharedCallStack::new(accounts.alice);
+            let fat_badges = Addressable::create_native(1, FatBadges::new(), stack.clone());
+            let plain = fat_badges
+                .call_mut()
+                .new_badge("plain".to_string())
+                .unwrap();
+            fat_badges
+                .call_mut()
+                .add_code(plain, vec!["code1".to_string()])
+                .unwrap();
+            let committed = fat_badges
+                .call_mut()
+                .new_badge("committed".to_string())
+                .unwrap();
+            fat_badges
+                .call_mut()
+                .set_code_mode(committed, CodeMode::Committed)
+                .unwrap();
+            fat_badges
+                .call_mut()
+                .add_code_hashes(committed, vec![hash_code("code2")])
+                .unwrap();
+            fat_badges.call_mut().issue(plain, accounts.django).unwrap();
+            fat_badges
+                .call_mut()
+                .issue(committed, accounts.django)
+                .unwrap();
+
+            // Anyone can check the membership
+            stack.switch_account(accounts.eve).unwrap();
+            assert!(fat_badges.call().has_badge(plain, accounts.django));
+            assert!(!fat_badges.call().has_badge(plain, accounts.eve));
+            assert!(!fat_badges.call().has_badge(2, accounts.django));
+            assert_eq!(fat_badges.call().my_badges(), vec![]);
+
+            // Django gets all the badges at once
+            stack.switch_account(accounts.django).unwrap();
+            let badges = fat_badges.call().my_badges();
+            assert_eq!(badges.len(), 2);
+            assert_eq!(badges[0].0, plain);
+            assert_eq!(badges[0].1.name, "plain");
+            assert_eq!(badges[0].2, Some("code1".to_string()));
+            assert_eq!(badges[1].0, committed);
+            assert_eq!(badges[1].2, None);
+
+            // Revoked and expired badges don't count
+            stack.switch_account(accounts.alice).unwrap();
+            fat_badges
+                .call_mut()
+                .revoke(plain, accounts.django, "mistake".to_string(), false)
+                .unwrap();
+            fat_badges
+                .call_mut()
+                .set_issuance_rules(committed, None, None, None, Some(0))
+                .unwrap();
+            assert!(!fat_badges.call().has_badge(plain, accounts.django));
+            assert!(!fat_badges.call().has_badge(committed, accounts.django));
+            stack.switch_account(accounts.django).unwrap();
+            assert_eq!(fat_badges.call().my_badges(), vec![]);
+        }
+
+        #[ink::test]
+        fn cross_contract_badge_query() {
+            use crate::badge_query::{mock_badgequery, BadgeQueryRef};
+
+            let accounts = default_accounts();
+            let stack = SharedCallStack::new(accounts.alice);
+            mock_badgequery::using(stack.clone(), || {
+                let badges = mock_badgequery::deploy(FatBadges::new());
+                let id = badges
+                    .call_mut()
+                    .new_badge("Phala Workshop: Easy".to_string())
+                    .unwrap();
+                badges
+                    .call_mut()
+                    .add_code(id, vec!["code1".to_string()])
+                    .unwrap();
+                badges.call_mut().issue(id, accounts.django).unwrap();
+
+                // Query from another contract
+                let query: &BadgeQueryRef = &badges.id();
+                assert!(query.has_badge(id, accounts.django));
+                assert!(!query.has_badge(id, accounts.eve));
+                assert_eq!(query.badge_count_of(accounts.django), 1);
+                assert_eq!(query.badge_count_of(accounts.eve), 0);
+                assert_eq!(
+                    query.issued_at(id, accounts.django),
+                    Some(ink_env::block_timestamp::<Environment>())
+                );
+                assert_eq!(query.issued_at(id, accounts.eve), None);
+            });
+        }
+
+        #[ink::test]
+        fn manage_roles() {
+            let accounts = default_accounts();
+
+            let stack = SharedCallStack::new(accounts.alice);
+            let fat_badges = Addressable::create_native(1, FatBadges::new(), stack.clone());
+            let id = fat_badges
+                .call_mut()
+                .new_badge("Phala Workshop: Easy".to_string())
+                .unwrap();
+
+            // The owner has all the roles known to the contract
+            assert!(fat_badges.call().has_role(OWNER, accounts.alice));
+            for role in OWNER_ROLES {
+                assert!(fat_badges.call().has_role(role, accounts.alice));
+            }
+            assert!(!fat_badges.call().has_role(42, accounts.alice));
+            assert!(!fat_badges.call().has_role(AUDITOR, accounts.bob));
+            assert_eq!(fat_badges.call().get_role_admin(ISSUER), OWNER);
+
+            // Only the owner can grant roles, except the owner role
+            stack.switch_account(accounts.bob).unwrap();
+            assert_eq!(
+                fat_badges.call_mut().grant_role(ISSUER, accounts.bob),
+                Err(AccessControlError::MissingRole)
+            );
+            stack.switch_account(accounts.alice).unwrap();
+            assert_eq!(
+                fat_badges.call_mut().grant_role(OWNER, accounts.bob),
+                Err(AccessControlError::InvalidCaller)
+            );
+            for (role, account) in [
+                (CODE_UPLOADER, accounts.bob),
+                (ISSUER, accounts.charlie),
+                (AUDITOR, accounts.django),
+            ] {
+                assert!(fat_badges.call_mut().grant_role(role, account).is_ok());
+            }
+            assert_eq!(
+                fat_badges.call_mut().grant_role(ISSUER, accounts.charlie),
+                Err(AccessControlError::RoleRedundant)
+            );
+            let events = recorded_events();
+            assert!(matches!(
+                events.last(),
+                Some(Event::RoleGranted(e))
+                    if e.role == AUDITOR && e.grantee == accounts.django && e.grantor == accounts.alice
+            ));
+            // The role overrides the allowance as a badge issuer
+            assert!(fat_badges
+                .call_mut()
+                .add_issuer(id, accounts.charlie, Some(0))
+                .is_ok());
+
+            // Each role unlocks its own permission on any badge
+            stack.switch_account(accounts.bob).unwrap();
+            assert!(fat_badges
+                .call_mut()
+                .add_code(id, vec!["code1".to_string(), "code2".to_string()])
+                .is_ok());
+            assert_eq!(
+                fat_badges.call_mut().issue(id, accounts.eve),
+                Err(Error::NotAnIssuer)
+            );
+            assert_eq!(
+                fat_badges.call().get_code_inventory(id),
+                Err(Error::BadOrigin)
+            );
+            stack.switch_account(accounts.charlie).unwrap();
+            assert!(fat_badges.call_mut().issue(id, accounts.eve).is_ok());
+            assert_eq!(
+                fat_badges
+                    .call_mut()
+                    .add_code(id, vec!["code3".to_string()]),
+                Err(Error::BadOrigin)
+            );
+            stack.switch_account(accounts.django).unwrap();
+            assert_eq!(fat_badges.call().get_code_inventory(id).unwrap().issued, 1);
+            assert_eq!(
+                fat_badges.call_mut().add_issuer(id, accounts.django, None),
+                Err(Error::BadOrigin)
+            );
+
+            // Roles can be revoked by the owner or renounced by the holder
+            assert_eq!(
+                fat_badges.call_mut().renounce_role(AUDITOR, accounts.bob),
+                Err(AccessControlError::InvalidCaller)
+            );
+            assert!(fat_badges
+                .call_mut()
+                .renounce_role(AUDITOR, accounts.django)
+                .is_ok());
+            assert_eq!(
+                fat_badges
+                    .call_mut()
+                    .renounce_role(AUDITOR, accounts.django),
+                Err(AccessControlError::MissingRole)
+            );
+            stack.switch_account(accounts.alice).unwrap();
+            assert!(fat_badges
+                .call_mut()
+                .revoke_role(ISSUER, accounts.charlie)
+                .is_ok());
+            assert_eq!(
+                fat_badges.call_mut().revoke_role(ISSUER, accounts.charlie),
+                Err(AccessControlError::MissingRole)
+            );
+            stack.switch_account(accounts.charlie).unwrap();
+            assert_eq!(
+                fat_badges.call_mut().issue(id, accounts.frank),
+                Err(Error::QuotaExceeded)
+            );
+        }
+
+        #[ink::test]
+        fn delegate_role_admin() {
+            const ISSUER_ADMIN: RoleType = 42;
+            let accounts = default_accounts();
+
+            let stack = SharedCallStack::new(accounts.alice);
+            let fat_badges = Addressable::create_native(1, FatBadges::new(), stack.clone());
+            assert_eq!(fat_badges.call().get_role_admin(ISSUER), OWNER);
+
+            // Only the owner can change the admin role, except that of the owner role
+            stack.switch_account(accounts.bob).unwrap();
+            assert_eq!(
+                fat_badges.call_mut().set_role_admin(ISSUER, ISSUER_ADMIN),
+                Err(Error::BadOrigin)
+            );
+            stack.switch_account(accounts.alice).unwrap();
+            assert_eq!(
+                fat_badges.call_mut().set_role_admin(OWNER, ISSUER_ADMIN),
+                Err(Error::BadOrigin)
+            );
+            assert!(fat_badges
+                .call_mut()
+                .set_role_admin(ISSUER, ISSUER_ADMIN)
+                .is_ok());
+            assert_eq!(fat_badges.call().get_role_admin(ISSUER), ISSUER_ADMIN);
+            assert!(matches!(
+                recorded_events().last(),
+                Some(Event::RoleAdminChanged(e))
+                    if e.role == ISSUER
+                        && e.previous_admin_role == OWNER
+                        && e.new_admin_role == ISSUER_ADMIN
+            ));
+            fat_badges
+                .call_mut()
+                .grant_role(ISSUER_ADMIN, accounts.bob)
+                .unwrap();
+
+            // Bob manages the issuers, but not the other roles
+            stack.switch_account(accounts.bob).unwrap();
+            assert!(fat_badges
+                .call_mut()
+                .grant_role(ISSUER, accounts.charlie)
+                .is_ok());
+            assert!(fat_badges.call().has_role(ISSUER, accounts.charlie));
+            assert_eq!(
+                fat_badges.call_mut().grant_role(AUDITOR, accounts.charlie),
+                Err(AccessControlError::MissingRole)
+            );
+            stack.switch_account(accounts.charlie).unwrap();
+            assert_eq!(
+                fat_badges.call_mut().grant_role(ISSUER, accounts.django),
+                Err(AccessControlError::MissingRole)
+            );
+            // The owner can still manage the role
+            stack.switch_account(accounts.alice).unwrap();
+            assert!(fat_badges
+                .call_mut()
+                .revoke_role(ISSUER, accounts.charlie)
+                .is_ok());
+
+            // Back to the default
+            fat_badges.call_mut().set_role_admin(ISSUER, OWNER).unwrap();
+            stack.switch_account(accounts.bob).unwrap();
+            assert_eq!(
+                fat_badges.call_mut().grant_role(ISSUER, accounts.charlie),
+                Err(AccessControlError::MissingRole)
+            );
+        }
+
+        #[ink::test]
+        fn owner_override() {
+            let accounts = default_accounts();
+
+            let stack = SharedCallStack::new(accounts.alice);
+            let fat_badges = Addressable::create_native(1, FatBadges::new(), stack.clone());
+            fat_badges
+                .call_mut()
+                .grant_role(BADGE_CREATOR, accounts.bob)
+                .unwrap();
+            stack.switch_account(accounts.bob).unwrap();
+            let id = fat_badges
+                .call_mut()
+                .new_badge("bob's".to_string())
+                .unwrap();
+            let series = fat_badges
+                .call_mut()
+                .new_series("bob's".to_string())
+                .unwrap();
+
+            // The owner can manage any badge and series in an emergency
+            stack.switch_account(accounts.alice).unwrap();
+            assert!(fat_badges
+                .call_mut()
+                .add_code(id, vec!["code1".to_string()])
+                .is_ok());
+            assert!(fat_badges.call_mut().issue(id, accounts.django).is_ok());
+            assert!(fat_badges
+                .call_mut()
+                .revoke(id, accounts.django, "fraud".to_string(), false)
+                .is_ok());
+            assert!(fat_badges.call_mut().freeze_badge(id).is_ok());
+            assert!(fat_badges.call_mut().add_to_series(series, id).is_ok());
+            assert_eq!(
+                fat_badges.call().get_badge_info(id).unwrap().admin,
+                accounts.bob
+            );
+            // Other accounts can't
+            stack.switch_account(accounts.charlie).unwrap();
+            assert_eq!(
+                fat_badges.call_mut().unfreeze_badge(id),
+                Err(Error::BadOrigin)
+            );
+            assert_eq!(
+                fat_badges.call_mut().remove_from_series(series, id),
+                Err(Error::BadOrigin)
+            );
+
+            // The override moves with the ownership
+            stack.switch_account(accounts.alice).unwrap();
+            fat_badges
+                .call_mut()
+                .transfer_ownership(accounts.eve)
+                .unwrap();
+            stack.switch_account(accounts.eve).unwrap();
+            fat_badges.call_mut().accept_ownership().unwrap();
+            assert!(fat_badges.call_mut().unfreeze_badge(id).is_ok());
+            stack.switch_account(accounts.alice).unwrap();
+            assert_eq!(
+                fat_badges.call_mut().freeze_badge(id),
+                Err(Error::BadOrigin)
+            );
+            assert!(!fat_badges.call().has_role(ISSUER, accounts.alice));
+            assert!(fat_badges.call().has_role(ISSUER, accounts.eve));
+        }
+
+        #[ink::test]
+        fn pause_and_freeze() {
+            let accounts = default_accounts();
+
+            let stack = SharedCallStack::new(accounts.alice);
+            let fat_badges = Addressable::create_native(1, FatBadges::new(), stack.clone());
+            fat_badges
+                .call_mut()
+                .grant_role(BADGE_CREATOR, accounts.bob)
+                .unwrap();
+            stack.switch_account(accounts.bob).unwrap();
+            let id = fat_badges
+                .call_mut()
+                .new_badge("Phala Workshop: Easy".to_string())
+                .unwrap();
+            fat_badges
+                .call_mut()
+                .add_code(id, vec!["code1".to_string(), "code2".to_string()])
+                .unwrap();
+            fat_badges
+                .call_mut()
+                .add_issuer(id, accounts.charlie, None)
+                .unwrap();
+
+            // Only the contract admin can pause the contract
+            assert_eq!(fat_badges.call_mut().pause(), Err(Error::BadOrigin));
+            stack.switch_account(accounts.alice).unwrap();
+            assert!(fat_badges.call_mut().pause().is_ok());
+            assert!(fat_badges.call().is_paused());
+
+            // Everything is blocked when paused
+            stack.switch_account(accounts.charlie).unwrap();
+            assert_eq!(
+                fat_badges.call_mut().issue(id, accounts.django),
+                Err(Error::Paused)
+            );
+            assert_eq!(
+                fat_badges.call_mut().issue_batch(id, vec![accounts.django]),
+                Err(Error::Paused)
+            );
+            stack.switch_account(accounts.bob).unwrap();
+            assert_eq!(
+                fat_badges
+                    .call_mut()
+                    .add_code(id, vec!["code3".to_string()]),
+                Err(Error::Paused)
+            );
+            assert_eq!(
+                fat_badges.call_mut().add_issuer(id, accounts.eve, None),
+                Err(Error::Paused)
+            );
+            assert_eq!(
+                fat_badges.call_mut().remove_issuer(id, accounts.charlie),
+                Err(Error::Paused)
+            );
+
+            stack.switch_account(accounts.alice).unwrap();
+            assert!(fat_badges.call_mut().unpause().is_ok());
+            assert!(!fat_badges.call().is_paused());
+
+            // Only the badge admin can freeze the badge
+            stack.switch_account(accounts.charlie).unwrap();
+            assert_eq!(
+                fat_badges.call_mut().freeze_badge(id),
+                Err(Error::BadOrigin)
+            );
+            assert!(fat_badges.call_mut().issue(id, accounts.django).is_ok());
+            stack.switch_account(accounts.bob).unwrap();
+            assert!(fat_badges.call_mut().freeze_badge(id).is_ok());
+            assert!(fat_badges.call().is_badge_frozen(id));
+            assert_eq!(
+                fat_badges.call_mut().issue(id, accounts.eve),
+                Err(Error::Frozen)
+            );
+            assert_eq!(
+                fat_badges
+                    .call_mut()
+                    .add_code(id, vec!["code3".to_string()]),
+                Err(Error::Frozen)
+            );
+            assert_eq!(
+                fat_badges.call_mut().remove_issuer(id, accounts.charlie),
+                Err(Error::Frozen)
+            );
+            // The issuers are blocked, but the unauthorized callers get the origin error first
+            stack.switch_account(accounts.charlie).unwrap();
+            assert_eq!(
+                fat_badges.call_mut().issue(id, accounts.eve),
+                Err(Error::Frozen)
+            );
+            stack.switch_account(accounts.django).unwrap();
+            assert_eq!(
+                fat_badges.call_mut().issue(id, accounts.eve),
+                Err(Error::NotAnIssuer)
+            );
+
+            // Unfreeze the badge
+            stack.switch_account(accounts.bob).unwrap();
+            assert!(fat_badges.call_mut().unfreeze_badge(id).is_ok());
+            assert!(!fat_badges.call().is_badge_frozen(id));
+            assert!(fat_badges.call_mut().issue(id, accounts.eve).is_ok());
+            let events = recorded_events();
+            assert!(matches!(
+                events.last(),
+                Some(Event::BadgeIssued(e)) if e.dest == accounts.eve
+            ));
+            assert!(events.iter().any(|e| matches!(
+                e,
+                Event::BadgeFreezeChanged(e) if e.id == id && !e.frozen
+            )));
+            assert!(events
+                .iter()
+                .any(|e| matches!(e, Event::PauseChanged(e) if e.paused)));
+        }
+
+        #[ink::test]
+        fn migrate_storage() {
+            use ink_primitives::Key;
+            use ink_storage::traits::{pull_spread_root, push_spread_root};
+
+            // The original layout before any upgrade
+            #[derive(Encode, Decode, SpreadLayout, PackedLayout)]
+            struct BaselineBadgeInfo {
+                id: u32,
+                admin: AccountId,
+                name: String,
+                num_code: u32,
+                num_issued: u32,
+            }
+            #[derive(SpreadLayout)]
+            struct BaselineFatBadges {
+                admin: AccountId,
+                total_badges: u32,
+                badge_info: Mapping<u32, BaselineBadgeInfo>,
+                badge_issuers: Mapping<(u32, AccountId), ()>,
+                badge_code: Mapping<(u32, u32), String>,
+                badge_assignments: Mapping<(u32, AccountId), u32>,
+            }
+
+            // Simulate the storage written by the original code: two badges held by Charlie,
+            // and Bob as an issuer of badge 0
+            let accounts = default_accounts();
+            let root = Key::from([0x00; 32]);
+            let baseline = BaselineFatBadges {
+                admin: accounts.alice,
+                total_badges: 2,
+                badge_info: Default::default(),
+                badge_issuers: Default::default(),
+                badge_code: Default::default(),
+                badge_assignments: Default::default(),
+            };
+            push_spread_root(&baseline, &root);
+            let mut baseline: BaselineFatBadges = pull_spread_root(&root);
+            for id in 0..2 {
+                baseline.badge_info.insert(
+                    id,
+                    &BaselineBadgeInfo {
+                        id,
+                        admin: accounts.alice,
+                        name: format!("badge{}", id),
+                        num_code: 3,
+                        num_issued: 1,
+                    },
+                );
+                for slot in 0..3 {
+                    baseline
+                        .badge_code
+                        .insert((id, slot), &format!("code{}", slot));
+                }
+                baseline
+                    .badge_assignments
+                    .insert((id, accounts.charlie), &0);
+            }
+            baseline.badge_issuers.insert((0, accounts.bob), &());
+
+            // The new code loads the old storage
+            let mut contract: FatBadges = pull_spread_root(&root);
+            assert_eq!(contract.get_storage_version(), 0);
+            assert_eq!(contract.get_admin(), accounts.alice);
+            assert_eq!(contract.get_pending_admin(), None);
+            assert!(!contract.is_paused());
+            assert_eq!(contract.issuer_allowance(0, accounts.bob), Ok(None));
+            // The old holders and issuers work as before
+            ink_env::test::set_caller::<Environment>(accounts.charlie);
+            assert_eq!(contract.get(0), Ok("code0".to_string()));
+            assert!(contract.has_badge(1, accounts.charlie));
+            assert_eq!(contract.get_issuance(0, accounts.charlie), None);
+            ink_env::test::set_caller::<Environment>(accounts.bob);
+            assert!(contract.issue(0, accounts.django).is_ok());
+            assert!(matches!(
+                contract.get_code_slot(0, accounts.django),
+                Some(1 | 2)
+            ));
+
+            // Only the admin can upgrade or migrate
+            assert_eq!(contract.set_code([1u8; 32]), Err(Error::BadOrigin));
+            assert_eq!(contract.migrate(0, 1), Err(Error::BadOrigin));
+            assert_eq!(
+                contract.migrate_accounts(0, vec![accounts.charlie]),
+                Err(Error::BadOrigin)
+            );
+
+            // Migrate in pages
+            ink_env::test::set_caller::<Environment>(accounts.alice);
+            assert_eq!(contract.migrate(0, 1), Ok(Some(1)));
+            assert_eq!(contract.get_storage_version(), 0);
+            assert_eq!(contract.migrate(1, 1), Ok(None));
+            assert_eq!(contract.get_storage_version(), STORAGE_VERSION);
+            assert!(matches!(
+                recorded_events().last(),
+                Some(Event::StorageMigrated(e)) if e.from == 0 && e.to == STORAGE_VERSION
+            ));
+            let badge = contract.get_badge_info(0).unwrap();
+            assert_eq!(badge.name, "badge0");
+            assert_eq!(badge.code_mode, CodeMode::Plaintext);
+            assert_eq!(badge.num_available(), 1);
+            // Migrating again is harmless
+            assert_eq!(contract.migrate(0, 10), Ok(None));
+
+            // Only the new holder is listed until the old accounts are added
+            assert_eq!(contract.list_holders(0, 0, 10), vec![accounts.django]);
+            assert!(contract.list_issuers(0, 0, 10).is_empty());
+            let old_accounts = vec![accounts.bob, accounts.charlie, accounts.eve];
+            assert!(contract.migrate_accounts(0, old_accounts.clone()).is_ok());
+            assert!(contract.migrate_accounts(1, old_accounts.clone()).is_ok());
+            assert!(contract.migrate_accounts(0, old_accounts).is_ok());
+            assert_eq!(
+                contract.list_holders(0, 0, 10),
+                vec![accounts.django, accounts.charlie]
+            );
+            assert_eq!(contract.list_holders(1, 0, 10), vec![accounts.charlie]);
+            assert_eq!(contract.list_issuers(0, 0, 10), vec![accounts.bob]);
+            assert_eq!(contract.badges_of(accounts.charlie, 0, 10), vec![0, 1]);
+
+            // The old holders have no issuance record to export, and never expire
+            let page = contract.export_assignments(0, 0, 10).unwrap();
+            let entries: Vec<SnapshotEntry> = Decode::decode(&mut &page[..]).unwrap();
+            assert!(entries.iter().any(|entry| matches!(
+                entry,
+                SnapshotEntry::Assignment { holder, issuance: None, .. }
+                    if *holder == accounts.charlie
+            )));
+            assert!(contract
+                .set_issuance_rules(0, None, None, None, Some(1))
+                .is_ok());
+            ink_env::test::advance_block::<Environment>();
+            assert!(contract.has_badge(0, accounts.charlie));
+            assert!(!contract.has_badge(0, accounts.django));
         }
-    }
 
-    #[cfg(test)]
-    mod tests {
-        use super::*;
-        use ink_lang as ink;
-        use openbrush::traits::mock::{Addressable, SharedCallStack};
+        #[ink::test]
+        fn export_and_import() {
+            let accounts = default_accounts();
 
-        fn default_accounts() -> ink_env::test::DefaultAccounts<ink_env::DefaultEnvironment> {
-            ink_env::test::default_accounts::<Environment>()
+            let stack = SharedCallStack::new(accounts.alice);
+            let old = Addressable::create_native(1, FatBadges::new(), stack.clone());
+            let new = Addressable::create_native(2, FatBadges::new(), stack.clone());
+
+            // A plaintext badge with returned and invalidated code
+            let plain = old.call_mut().new_badge("plain".to_string()).unwrap();
+            let codes = (0..4).map(|i| format!("code{}", i)).collect();
+            old.call_mut().add_code(plain, codes).unwrap();
+            old.call_mut()
+                .add_issuer(plain, accounts.bob, Some(3))
+                .unwrap();
+            for holder in [accounts.django, accounts.eve, accounts.frank] {
+                old.call_mut().issue(plain, holder).unwrap();
+            }
+            let eve_slot = old.call().get_code_slot(plain, accounts.eve).unwrap();
+            old.call_mut()
+                .revoke(plain, accounts.eve, "mistake".to_string(), true)
+                .unwrap();
+            let drawn = [accounts.django, accounts.frank]
+                .map(|holder| old.call().get_code_slot(plain, holder).unwrap());
+            let fresh_slot = (0..4)
+                .find(|slot| *slot != eve_slot && !drawn.contains(slot))
+                .unwrap();
+            old.call_mut()
+                .invalidate_code(plain, fresh_slot, fresh_slot + 1)
+                .unwrap();
+            // A committed badge with delivered code
+            let committed = old.call_mut().new_badge("committed".to_string()).unwrap();
+            old.call_mut()
+                .set_code_mode(committed, CodeMode::Committed)
+                .unwrap();
+            old.call_mut()
+                .add_code_hashes(committed, vec![hash_code("code0"), hash_code("code1")])
+                .unwrap();
+            old.call_mut().issue(committed, accounts.charlie).unwrap();
+            old.call_mut()
+                .deliver_code(committed, accounts.charlie, b"sealed".to_vec())
+                .unwrap();
+            // Both in a series, and the committed badge is frozen
+            old.call_mut().new_series("empty".to_string()).unwrap();
+            let series = old.call_mut().new_series("series".to_string()).unwrap();
+            for id in [committed, plain] {
+                old.call_mut().add_to_series(series, id).unwrap();
+            }
+            old.call_mut().freeze_badge(committed).unwrap();
+
+            // Only the admin can export and import
+            stack.switch_account(accounts.bob).unwrap();
+            assert_eq!(old.call().export_badges(0, 10), Err(Error::BadOrigin));
+            assert_eq!(new.call_mut().import(vec![]), Err(Error::BadOrigin));
+            stack.switch_account(accounts.alice).unwrap();
+            assert_eq!(old.call_mut().import(vec![]), Err(Error::ImportClosed));
+
+            // Bad snapshots are rejected as a whole
+            assert_eq!(
+                new.call_mut().import(vec![1, 2, 3]),
+                Err(Error::InvalidSnapshot)
+            );
+            let issuers = old.call().export_issuers(plain, 0, 10).unwrap();
+            assert_eq!(
+                new.call_mut().import(issuers),
+                Err(Error::InvalidSnapshot),
+                "The badges must be imported first"
+            );
+            let badges = old.call().export_badges(1, 1).unwrap();
+            assert_eq!(
+                new.call_mut().import(badges),
+                Err(Error::InvalidSnapshot),
+                "The badges must be imported in order"
+            );
+            assert_eq!(new.call().get_total_badges(), 0);
+
+            // Copy the state page by page
+            let badges = old.call().export_badges(0, 2).unwrap();
+            assert!(new.call_mut().import(badges).is_ok());
+            let series_badges = old.call().export_series_badges(series, 0, 10).unwrap();
+            assert_eq!(
+                new.call_mut().import(series_badges),
+                Err(Error::InvalidSnapshot),
+                "The series must be imported first"
+            );
+            assert_eq!(
+                new.call_mut()
+                    .import(vec![SnapshotEntry::Frozen { id: 2 }].encode()),
+                Err(Error::InvalidSnapshot),
+                "The badge must be imported first"
+            );
+            assert_eq!(new.call().get_total_badges(), 2);
+
+            let mut pages = vec![
+                old.call().export_series(0, 1).unwrap(),
+                old.call().export_series(1, 1).unwrap(),
+                old.call().export_series_badges(series, 0, 1).unwrap(),
+                old.call().export_series_badges(series, 1, 1).unwrap(),
+            ];
+            for id in [plain, committed] {
+                pages.push(old.call().export_issuers(id, 0, 10).unwrap());
+                pages.push(old.call().export_code(id, 0, 2).unwrap());
+                pages.push(old.call().export_code(id, 2, 2).unwrap());
+                pages.push(old.call().export_assignments(id, 0, 10).unwrap());
+            }
+            for page in pages {
+                assert!(new.call_mut().import(page).is_ok());
+            }
+
+            // The state is identical
+            assert_eq!(new.call().get_total_badges(), 2);
+            assert_eq!(new.call().get_total_series(), 2);
+            assert_eq!(
+                new.call().get_series_info(series),
+                old.call().get_series_info(series)
+            );
+            assert_eq!(
+                new.call().list_series_badges(series, 0, 10),
+                vec![committed, plain]
+            );
+            assert_eq!(new.call().get_badge_series(plain), Some(series));
+            assert!(new.call().is_badge_frozen(committed));
+            assert!(!new.call().is_badge_frozen(plain));
+            let series_badges = old.call().export_series_badges(series, 0, 10).unwrap();
+            assert_eq!(
+                new.call_mut().import(series_badges),
+                Err(Error::InvalidSnapshot),
+                "A badge can be in at most one series"
+            );
+            for id in [plain, committed] {
+                let expected = (
+                    old.call().get_badge_info(id),
+                    old.call().get_code_inventory(id),
+                    old.call().list_issuers(id, 0, 10),
+                    old.call().list_holders(id, 0, 10),
+                );
+                let actual = (
+                    new.call().get_badge_info(id),
+                    new.call().get_code_inventory(id),
+                    new.call().list_issuers(id, 0, 10),
+                    new.call().list_holders(id, 0, 10),
+                );
+                assert_eq!(actual, expected);
+            }
+            assert_eq!(
+                new.call().issuer_allowance(plain, accounts.bob),
+                Ok(Some(3))
+            );
+            assert_eq!(new.call().badges_of(accounts.django, 0, 10), vec![plain]);
+            stack.switch_account(accounts.django).unwrap();
+            let django_code = old.call().get(plain);
+            assert_eq!(new.call().get(plain), django_code);
+            stack.switch_account(accounts.charlie).unwrap();
+            assert_eq!(
+                new.call().get_sealed_code(committed),
+                Ok(b"sealed".to_vec())
+            );
+
+            // Only the returned code is left to issue
+            stack.switch_account(accounts.alice).unwrap();
+            assert!(new.call_mut().issue(plain, accounts.eve).is_ok());
+            assert_eq!(
+                new.call().get_code_slot(plain, accounts.eve),
+                Some(eve_slot)
+            );
+            assert_eq!(
+                new.call_mut().issue(plain, accounts.alice),
+                Err(Error::RunOutOfCode)
+            );
+
+            // The import is closed once a badge is created
+            new.call_mut().new_badge("new".to_string()).unwrap();
+            let badges = old.call().export_badges(0, 1).unwrap();
+            assert_eq!(new.call_mut().import(badges), Err(Error::ImportClosed));
         }
 
         #[ink::test]
-        fn issue_badges() {
+        fn validate_import() {
             let accounts = default_accounts();
 
             let stack = SharedCallStack::new(accounts.alice);
-            let fat_badges = Addressable::create_native(1, FatBadges::new(), stack.clone());
-            assert_eq!(fat_badges.call().admin, accounts.alice);
+            let old = Addressable::create_native(1, FatBadges::new(), stack.clone());
+            let new = Addressable::create_native(2, FatBadges::new(), stack.clone());
+            let id = old.call_mut().new_badge("plain".to_string()).unwrap();
+            old.call_mut().new_badge("other".to_string()).unwrap();
+            let codes = vec!["code0".to_string(), "code1".to_string()];
+            old.call_mut().add_code(id, codes).unwrap();
+            old.call_mut().issue(id, accounts.django).unwrap();
+            let decode =
+                |page: Vec<u8>| -> Vec<SnapshotEntry> { Decode::decode(&mut &page[..]).unwrap() };
+            // Make the first badge depend on the second one
+            let mut badges = decode(old.call().export_badges(0, 1).unwrap());
+            if let SnapshotEntry::Badge(badge) = &mut badges[0] {
+                badge.prerequisites = vec![1];
+            }
+            let assignments = decode(old.call().export_assignments(id, 0, 10).unwrap());
 
-            // Alice can create a badge
-            let id = fat_badges
-                .call_mut()
-                .new_badge("Phala Workshop: Easy".to_string())
-                .expect("Should be able to create badges");
+            // The code slot must exist
+            let mut bad_slot = assignments.clone();
+            if let SnapshotEntry::Assignment { slot, .. } = &mut bad_slot[0] {
+                *slot = 2;
+            }
+            assert_eq!(
+                new.call_mut()
+                    .import([badges.clone(), bad_slot].concat().encode()),
+                Err(Error::InvalidSnapshot)
+            );
+            assert_eq!(
+                new.call_mut().import(
+                    [
+                        badges.clone(),
+                        vec![SnapshotEntry::SealedCode {
+                            id,
+                            slot: 2,
+                            sealed_code: vec![]
+                        }]
+                    ]
+                    .concat()
+                    .encode()
+                ),
+                Err(Error::InvalidSnapshot)
+            );
+            assert!(new.call_mut().import(badges.encode()).is_ok());
 
-            // Can add an issuer
-            assert!(fat_badges.call_mut().add_issuer(id, accounts.bob).is_ok());
+            // The holders can't be missing, duplicated or more than issued
+            assert_eq!(new.call_mut().finish_import(), Err(Error::InvalidSnapshot));
+            assert_eq!(
+                new.call_mut()
+                    .import([assignments.clone(), assignments.clone()].concat().encode()),
+                Err(Error::InvalidSnapshot)
+            );
+            let extra = SnapshotEntry::Assignment {
+                id,
+                holder: accounts.eve,
+                slot: 1,
+                issuance: None,
+                sealed_code: None,
+            };
+            assert_eq!(
+                new.call_mut()
+                    .import([assignments.clone(), vec![extra]].concat().encode()),
+                Err(Error::InvalidSnapshot)
+            );
+            assert!(new.call_mut().import(assignments.encode()).is_ok());
+            assert_eq!(
+                new.call_mut().import(assignments.encode()),
+                Err(Error::InvalidSnapshot)
+            );
+
+            // The prerequisite must be imported before closing the import in any way
+            assert_eq!(new.call_mut().finish_import(), Err(Error::InvalidSnapshot));
+            assert_eq!(
+                new.call_mut().new_badge("new".to_string()),
+                Err(Error::InvalidSnapshot)
+            );
+            assert_eq!(
+                new.call_mut().new_series("new".to_string()),
+                Err(Error::InvalidSnapshot)
+            );
+            let other = old.call().export_badges(1, 1).unwrap();
+            assert!(new.call_mut().import(other).is_ok());
+            assert!(new.call_mut().new_series("new".to_string()).is_ok());
+            assert_eq!(
+                new.call_mut().import(Vec::<SnapshotEntry>::new().encode()),
+                Err(Error::ImportClosed)
+            );
+        }
+
+        #[ink::test]
+        fn badge_series() {
+            let accounts = default_accounts();
+
+            let stack = SharedCallStack::new(accounts.alice);
+            let fat_badges = Addressable::create_native(1, FatBadges::new(), stack.clone());
+            let codes: Vec<_> = (0..2).map(|i| format!("code{}", i)).collect();
+            for name in ["easy", "advanced", "other"] {
+                let id = fat_badges.call_mut().new_badge(name.to_string()).unwrap();
+                fat_badges.call_mut().add_code(id, codes.clone()).unwrap();
+            }
+            fat_badges
+                .call_mut()
+                .grant_role(BADGE_CREATOR, accounts.bob)
+                .unwrap();
 
-            // Bob can create another badge
+            // Bob creates a series, but needs Alice to add her badges
+            stack.switch_account(accounts.charlie).unwrap();
+            assert_eq!(
+                fat_badges.call_mut().new_series("workshop".to_string()),
+                Err(Error::BadOrigin)
+            );
             stack.switch_account(accounts.bob).unwrap();
-            let id_adv = fat_badges
+            let series = fat_badges
                 .call_mut()
-                .new_badge("Phala Workshop: Advanced".to_string())
-                .expect("Should be able to create badges");
+                .new_series("workshop".to_string())
+                .unwrap();
+            assert_eq!(fat_badges.call().get_total_series(), 1);
+            assert_eq!(
+                fat_badges.call().get_series_info(series),
+                Ok(SeriesInfo {
+                    id: series,
+                    admin: accounts.bob,
+                    name: "workshop".to_string(),
+                })
+            );
+            assert_eq!(
+                fat_badges.call_mut().add_to_series(series, 0),
+                Err(Error::BadOrigin)
+            );
+            assert_eq!(
+                fat_badges.call_mut().add_to_series(1, 0),
+                Err(Error::SeriesNotFound)
+            );
             stack.switch_account(accounts.alice).unwrap();
+            for id in [0, 1, 2] {
+                assert!(fat_badges.call_mut().add_to_series(series, id).is_ok());
+            }
             assert_eq!(
-                fat_badges.call_mut().add_issuer(id_adv, accounts.bob),
-                Err(Error::BadOrigin),
-                "Only the badge owner can add issuers"
+                fat_badges.call_mut().add_to_series(series, 0),
+                Err(Error::AlreadyInSeries)
             );
+            assert_eq!(fat_badges.call().get_badge_series(1), Some(series));
             assert_eq!(
-                fat_badges.call_mut().add_issuer(999, accounts.bob),
-                Err(Error::BadgeNotFound),
-                "Non-existing badge"
+                fat_badges.call().list_series_badges(series, 0, 10),
+                vec![0, 1, 2]
             );
 
-            // Can remove an issuer
-            assert!(fat_badges
-                .call_mut()
-                .add_issuer(id, accounts.charlie)
-                .is_ok());
-            assert!(fat_badges
+            // The series admin can remove a badge
+            stack.switch_account(accounts.bob).unwrap();
+            assert!(fat_badges.call_mut().remove_from_series(series, 2).is_ok());
+            assert_eq!(
+                fat_badges.call_mut().remove_from_series(series, 2),
+                Err(Error::NotFound)
+            );
+            assert_eq!(fat_badges.call().get_badge_series(2), None);
+            let events = recorded_events();
+            assert!(matches!(
+                events.last(),
+                Some(Event::SeriesBadgeRemoved(e)) if e.series_id == series && e.badge_id == 2
+            ));
+
+            // Track the progress of a holder
+            stack.switch_account(accounts.alice).unwrap();
+            let progress = |account| fat_badges.call().series_progress(series, account).unwrap();
+            assert_eq!(
+                progress(accounts.django),
+                SeriesProgress { held: 0, total: 2 }
+            );
+            fat_badges.call_mut().issue(0, accounts.django).unwrap();
+            fat_badges.call_mut().issue(2, accounts.django).unwrap();
+            assert_eq!(
+                progress(accounts.django),
+                SeriesProgress { held: 1, total: 2 }
+            );
+            assert!(!progress(accounts.django).completed());
+            fat_badges.call_mut().issue(1, accounts.django).unwrap();
+            assert!(progress(accounts.django).completed());
+            // Revoked badges don't count
+            fat_badges
                 .call_mut()
-                .remove_issuer(id, accounts.charlie)
-                .is_ok());
-            assert!(!fat_badges.call().is_badge_issuer(id, accounts.charlie));
+                .revoke(1, accounts.django, "mistake".to_string(), false)
+                .unwrap();
+            assert!(!progress(accounts.django).completed());
+            assert_eq!(
+                fat_badges.call().series_progress(1, accounts.django),
+                Err(Error::SeriesNotFound)
+            );
+        }
 
-            // Can add code
+        #[ink::test]
+        fn composite_badges() {
+            let accounts = default_accounts();
+
+            let stack = SharedCallStack::new(accounts.alice);
+            let fat_badges = Addressable::create_native(1, FatBadges::new(), stack.clone());
+            for (name, num_code) in [("easy", 2), ("advanced", 2), ("meta", 1), ("super", 1)] {
+                let id = fat_badges.call_mut().new_badge(name.to_string()).unwrap();
+                let codes = (0..num_code).map(|i| format!("{}{}", name, i)).collect();
+                fat_badges.call_mut().add_code(id, codes).unwrap();
+            }
+            for id in [0, 1] {
+                fat_badges
+                    .call_mut()
+                    .add_issuer(id, accounts.bob, Some(1))
+                    .unwrap();
+            }
+
+            // Meta requires easy and advanced, and super requires meta
             assert!(fat_badges
                 .call_mut()
-                .add_code(id, vec!["code1".to_string(), "code2".to_string()])
+                .set_prerequisites(2, vec![0, 1])
                 .is_ok());
+            assert!(fat_badges.call_mut().set_prerequisites(3, vec![2]).is_ok());
+            assert_eq!(fat_badges.call().list_dependents(0, 0, 10), vec![2]);
+            assert_eq!(fat_badges.call().list_dependents(2, 0, 10), vec![3]);
+            for prerequisites in [vec![9], vec![1, 1], vec![3], vec![2], vec![0; 9]] {
+                assert_eq!(
+                    fat_badges.call_mut().set_prerequisites(0, prerequisites),
+                    Err(Error::InvalidPrerequisites)
+                );
+            }
             stack.switch_account(accounts.bob).unwrap();
             assert_eq!(
-                fat_badges.call_mut().add_code(id, vec![]),
-                Err(Error::BadOrigin),
-                "Only the badge owner can add code"
+                fat_badges.call_mut().set_prerequisites(1, vec![0]),
+                Err(Error::BadOrigin)
             );
 
-            // Check the badge stats
-            let badge = fat_badges.call().get_badge_info(id).unwrap();
-            assert_eq!(badge.num_code, 2);
-            assert_eq!(badge.num_issued, 0);
+            // Django receives meta and super with the last prerequisite
+            fat_badges.call_mut().issue(0, accounts.django).unwrap();
+            assert!(!fat_badges.call().has_badge(2, accounts.django));
+            fat_badges.call_mut().issue(1, accounts.django).unwrap();
+            assert!(fat_badges.call().has_badge(2, accounts.django));
+            assert!(fat_badges.call().has_badge(3, accounts.django));
+            assert_eq!(fat_badges.call().badges_of(accounts.django, 0, 10).len(), 4);
+            let events = recorded_events();
+            assert!(matches!(
+                &events[events.len() - 3..],
+                [Event::BadgeIssued(a), Event::BadgeIssued(b), Event::BadgeIssued(c)]
+                    if (a.id, b.id, c.id) == (1, 2, 3)
+            ));
+            // Only the allowance of the issued prerequisite is consumed
+            assert_eq!(
+                fat_badges.call().issuer_allowance(1, accounts.bob),
+                Ok(Some(0))
+            );
 
-            // Can issue badges to Django and Eve
+            // Eve doesn't get meta when it runs out of code
             stack.switch_account(accounts.alice).unwrap();
-            assert!(fat_badges.call_mut().issue(id, accounts.django).is_ok());
+            assert!(fat_badges
+                .call_mut()
+                .issue_batch(0, vec![accounts.eve])
+                .is_ok());
+            assert!(fat_badges.call_mut().issue(1, accounts.eve).is_ok());
+            assert!(!fat_badges.call().has_badge(2, accounts.eve));
+
+            // Back to a normal badge
+            assert!(fat_badges.call_mut().set_prerequisites(2, vec![]).is_ok());
+            assert_eq!(fat_badges.call().list_dependents(0, 0, 10), vec![]);
+            assert_eq!(fat_badges.call().list_dependents(1, 0, 10), vec![]);
+        }
+
+        #[ink::test]
+        fn approve_dependents() {
+            let accounts = default_accounts();
+
+            let stack = SharedCallStack::new(accounts.alice);
+            let fat_badges = Addressable::create_native(1, FatBadges::new(), stack.clone());
+            let easy = fat_badges.call_mut().new_badge("easy".to_string()).unwrap();
+            fat_badges
+                .call_mut()
+                .grant_role(BADGE_CREATOR, accounts.bob)
+                .unwrap();
+            stack.switch_account(accounts.bob).unwrap();
+            let meta = fat_badges.call_mut().new_badge("meta".to_string()).unwrap();
+
+            // Bob can't depend on Alice's badge without her approval
             assert_eq!(
-                fat_badges.call_mut().issue(id, accounts.django),
-                Err(Error::Duplicated),
-                "Cannot issue duplicated badges"
+                fat_badges.call_mut().set_prerequisites(meta, vec![easy]),
+                Err(Error::BadOrigin)
             );
-            stack.switch_account(accounts.bob).unwrap();
-            assert!(fat_badges.call_mut().issue(id, accounts.eve).is_ok());
             assert_eq!(
-                fat_badges.call_mut().issue(id, accounts.frank),
-                Err(Error::RunOutOfCode),
-                "No code available to issue badges"
+                fat_badges.call_mut().approve_dependent(easy, meta, true),
+                Err(Error::BadOrigin)
+            );
+            stack.switch_account(accounts.alice).unwrap();
+            assert_eq!(
+                fat_badges.call_mut().approve_dependent(easy, 9, true),
+                Err(Error::BadgeNotFound)
             );
+            assert!(fat_badges
+                .call_mut()
+                .approve_dependent(easy, meta, true)
+                .is_ok());
+            assert!(matches!(
+                recorded_events().last(),
+                Some(Event::DependentApproved(e)) if (e.id, e.dependent, e.approved) == (easy, meta, true)
+            ));
+            stack.switch_account(accounts.bob).unwrap();
+            assert!(fat_badges
+                .call_mut()
+                .set_prerequisites(meta, vec![easy])
+                .is_ok());
+            assert_eq!(fat_badges.call().list_dependents(easy, 0, 10), vec![meta]);
 
-            // Adding a new code solves the problem
+            // The disapproval only stops adding it again
             stack.switch_account(accounts.alice).unwrap();
             assert!(fat_badges
                 .call_mut()
-                .add_code(id, vec!["code3".to_string()])
+                .approve_dependent(easy, meta, false)
                 .is_ok());
-            assert!(fat_badges.call_mut().issue(id, accounts.frank).is_ok());
+            stack.switch_account(accounts.bob).unwrap();
+            assert!(fat_badges
+                .call_mut()
+                .set_prerequisites(meta, vec![easy])
+                .is_ok());
+            assert!(fat_badges
+                .call_mut()
+                .set_prerequisites(meta, vec![])
+                .is_ok());
+            assert_eq!(
+                fat_badges.call_mut().set_prerequisites(meta, vec![easy]),
+                Err(Error::BadOrigin)
+            );
 
-            // Code can be revealed
-            stack.switch_account(accounts.django).unwrap();
-            assert_eq!(fat_badges.call().get(id), Ok("code1".to_string()));
-            stack.switch_account(accounts.eve).unwrap();
-            assert_eq!(fat_badges.call().get(id), Ok("code2".to_string()));
-            stack.switch_account(accounts.frank).unwrap();
-            assert_eq!(fat_badges.call().get(id), Ok("code3".to_string()));
+            // Even the contract admin can't exceed the dependents of a badge
             stack.switch_account(accounts.alice).unwrap();
-            assert_eq!(fat_badges.call().get(id), Err(Error::NotFound));
+            for i in 0..=MAX_DEPENDENTS {
+                let id = fat_badges
+                    .call_mut()
+                    .new_badge(format!("dependent{}", i))
+                    .unwrap();
+                let result = fat_badges.call_mut().set_prerequisites(id, vec![easy]);
+                if i < MAX_DEPENDENTS {
+                    assert!(result.is_ok());
+                } else {
+                    assert_eq!(result, Err(Error::InvalidPrerequisites));
+                }
+            }
+            assert_eq!(
+                fat_badges.call().list_dependents(easy, 0, 100).len(),
+                MAX_DEPENDENTS as usize
+            );
+        }
 
-            // Final checks
-            let badge = fat_badges.call().get_badge_info(id).unwrap();
-            assert_eq!(badge.num_code, 3);
-            assert_eq!(badge.num_issued, 3);
-            assert_eq!(fat_badges.call().get_total_badges(), 2);
+        #[ink::test]
+        fn issuance_records() {
+            let accounts = default_accounts();
+
+            let stack = SharedCallStack::new(accounts.alice);
+            let fat_badges = Addressable::create_native(1, FatBadges::new(), stack.clone());
+            let codes: Vec<_> = (0..2).map(|i| format!("code{}", i)).collect();
+            for name in ["easy", "meta"] {
+                let id = fat_badges.call_mut().new_badge(name.to_string()).unwrap();
+                fat_badges.call_mut().add_code(id, codes.clone()).unwrap();
+            }
+            fat_badges.call_mut().set_prerequisites(1, vec![0]).unwrap();
+            fat_badges
+                .call_mut()
+                .add_issuer(0, accounts.bob, None)
+                .unwrap();
+
+            // Bob issues the badge, and the contract issues the composite badge
+            ink_env::test::advance_block::<Environment>();
+            let block_number = ink_env::block_number::<Environment>();
+            let timestamp = ink_env::block_timestamp::<Environment>();
+            stack.switch_account(accounts.bob).unwrap();
+            fat_badges.call_mut().issue(0, accounts.django).unwrap();
+            assert_eq!(
+                fat_badges.call().get_issuance(0, accounts.django),
+                Some(Issuance {
+                    timestamp,
+                    block_number,
+                    issuer: accounts.bob,
+                })
+            );
+            assert_eq!(
+                fat_badges.call().get_issuance(1, accounts.django),
+                Some(Issuance {
+                    timestamp,
+                    block_number,
+                    issuer: fat_badges.id(),
+                })
+            );
+            assert_eq!(
+                fat_badges.call().get_issued_at(0, accounts.django),
+                Some(timestamp)
+            );
+            assert_eq!(fat_badges.call().get_issuance(0, accounts.eve), None);
+            // The code is revealed as usual
+            stack.switch_account(accounts.django).unwrap();
+            assert!(fat_badges.call().get(0).is_ok());
+
+            // The issuance is removed on revocation
+            stack.switch_account(accounts.alice).unwrap();
+            fat_badges
+                .call_mut()
+                .revoke(0, accounts.django, "mistake".to_string(), false)
+                .unwrap();
+            assert_eq!(fat_badges.call().get_issuance(0, accounts.django), None);
         }
     }
 }
@@ -8,11 +8,38 @@ const CONTRACT_NAMES = [
     ['advanced_judger', 'AdvancedJudger'],
 ]
 
+// The ABI the scripts depend on: the messages with their number of args, and the custom types
+const EXPECTED_ABI = {
+    fat_badges: { messages: [['new_badge', 1], ['add_code', 2], ['add_issuer', 3]], types: [] },
+    easy_oracle: { messages: [['config_issuer', 2]], types: ['IdentityQuote'] },
+    advanced_judger: { messages: [['config_issuer', 2]], types: [] },
+}
+
+// Fails early if the artifacts were built from an older version of the contracts
+function checkAbi(name, metadata) {
+    const { messages, types } = EXPECTED_ABI[name];
+    const stale = (what) => new Error(
+        `Stale ${name} artifacts (${what}). Rebuild them by scripts/build.sh and scripts/collect-bin.sh`
+    );
+    for (const [label, numArgs] of messages) {
+        const message = metadata.V3.spec.messages.find(m => m.label == label);
+        if (!message || message.args.length != numArgs) {
+            throw stale(`${label} should take ${numArgs} args`);
+        }
+    }
+    for (const type of types) {
+        if (!metadata.V3.types.some(t => t.type.path && t.type.path.at(-1) == type)) {
+            throw stale(`missing type ${type}`);
+        }
+    }
+}
+
 function loadContract(name) {
     const wasmPath = `../../target/ink/${name}/${name}.wasm`;
     const metadataPath = `../../target/ink/${name}/metadata.json`;
     const wasm = hex(fs.readFileSync(wasmPath, 'hex'));
     const metadata = JSON.parse(fs.readFileSync(metadataPath));
+    checkAbi(name, metadata);
     const constructor = metadata.V3.spec.constructors.find(c => c.label == 'new').selector;
     return {wasm, metadata, constructor};
 }
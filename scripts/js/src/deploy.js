@@ -31,8 +31,12 @@ async function main() {
         provider: wsProvider,
         types: {
             ...Phala.types,
-            'GistQuote': {
-                username: 'String',
+            'Provider': {
+                _enum: ['GithubGist', 'GitlabSnippet', 'WellKnown'],
+            },
+            'IdentityQuote': {
+                provider: 'Provider',
+                handle: 'String',
                 accountId: 'AccountId',
             },
         }
@@ -80,8 +84,8 @@ async function main() {
             FatBadges.tx.addCode({}, easyBadgeId, codeEasy),
             FatBadges.tx.addCode({}, advBadgeId, codeAdv),
             // set the issuers
-            FatBadges.tx.addIssuer({}, easyBadgeId, artifacts.EasyOracle.address),
-            FatBadges.tx.addIssuer({}, advBadgeId, artifacts.AdvancedJudger.address),
+            FatBadges.tx.addIssuer({}, easyBadgeId, artifacts.EasyOracle.address, null),
+            FatBadges.tx.addIssuer({}, advBadgeId, artifacts.AdvancedJudger.address, null),
             // config the issuers
             EasyOracle.tx.configIssuer({}, artifacts.FatBadges.address, easyBadgeId),
             AdvancedJudger.tx.configIssuer({}, artifacts.FatBadges.address, advBadgeId),
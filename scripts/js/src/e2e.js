@@ -70,8 +70,12 @@ async function main() {
         provider: wsProvider,
         types: {
             ...Phala.types,
-            'GistQuote': {
-                username: 'String',
+            'Provider': {
+                _enum: ['GithubGist', 'GitlabSnippet', 'WellKnown'],
+            },
+            'IdentityQuote': {
+                provider: 'Provider',
+                handle: 'String',
                 accountId: 'AccountId',
             },
         }
@@ -126,8 +130,8 @@ async function main() {
             FatBadges.tx.addCode({}, easyBadgeId, ['easy1', 'easy2']),
             FatBadges.tx.addCode({}, advBadgeId, ['adv1', 'adv2']),
             // set the issuers
-            FatBadges.tx.addIssuer({}, easyBadgeId, artifacts.EasyOracle.address),
-            FatBadges.tx.addIssuer({}, advBadgeId, artifacts.AdvancedJudger.address),
+            FatBadges.tx.addIssuer({}, easyBadgeId, artifacts.EasyOracle.address, null),
+            FatBadges.tx.addIssuer({}, advBadgeId, artifacts.AdvancedJudger.address, null),
             // config the issuers
             EasyOracle.tx.configIssuer({}, artifacts.FatBadges.address, easyBadgeId),
             AdvancedJudger.tx.configIssuer({}, artifacts.FatBadges.address, advBadgeId),
@@ -161,7 +165,7 @@ async function main() {
         'Easy attestation:',
         attest.result.isOk ? attest.output.toHuman() : attest.result.toHuman()
     );
-    console.log(EasyOracle.registry.createType('GistQuote', attest.output.asOk.data.toHex()).toHuman());
+    console.log(EasyOracle.registry.createType('IdentityQuote', attest.output.asOk.data.toHex()).toHuman());
     const attestObj = attest.output.asOk;
 
     // submit attestation